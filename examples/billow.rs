@@ -1,36 +1,35 @@
 // example.rs
 
-extern crate noise;
-extern crate image;
-extern crate time;
-
 use noise::gen::NoiseGen;
 use noise::gen::billow::Billow;
-use image::GenericImage;
-use std::io::File;
-use time::precise_time_s;
+use std::time::Instant;
 
 fn main() {
-    let mut ngen = Billow::new_rand(24, 0.5, 2.5, 100.0);
+    let ngen = Billow::builder()
+        .octaves(24)
+        .persistence(0.5)
+        .lacunarity(2.5)
+        .zoom(100.0)
+        .build()
+        .unwrap();
 
     println!("Noise seed is {}", ngen.get_seed());
     
-    let img_size = 512 as u32;
-    let mut imbuf = image::ImageBuf::new(img_size, img_size);
+    let img_size = 512_u32;
+    let mut imbuf = image::GrayImage::new(img_size, img_size);
 
-    let start = precise_time_s();
-    for x in range(0, img_size) {
-        for y in range(0, img_size) {
-            let n = ngen.get_value2d((x as f64), (y as f64));
+    let start = Instant::now();
+    for x in 0..img_size {
+        for y in 0..img_size {
+            let n = ngen.get_value2d(x as f64, y as f64);
             let col = (n * 255.0) as u8;
-            let pixel = image::Luma(col);
+            let pixel = image::Luma([col]);
             imbuf.put_pixel(x, y, pixel);
         }
     }
-    let end = precise_time_s();
+    let elapsed = start.elapsed();
 
-    let fout = File::create(&Path::new("billow.png")).unwrap();
-    let _ = image::ImageLuma8(imbuf).save(fout, image::PNG);
+    imbuf.save("billow.png").unwrap();
     println!("billow.png saved");
-    println!("generated {} points in {} ms", img_size*img_size, (end-start)*1000.0);
+    println!("generated {} points in {} ms", img_size*img_size, elapsed.as_secs_f64()*1000.0);
 }
@@ -1,45 +1,33 @@
 // step.rs
 
-extern crate noise;
-extern crate image;
-extern crate time;
-
 use noise::gen::ridgedmulti::RidgedMulti;
-use noise::utils::circle;
-use image::GenericImage;
-use std::io::File;
-use time::precise_time_s;
+use noise::gen::NoiseGen;
+use noise::gen::radialmask::RadialMask;
+use std::time::Instant;
 
 fn main() {
-    let mut inside = RidgedMulti::new_rand(24, 1.7, 1.9, 1.0, 0.75, 100.0);
-    let mut outside: f64 = 0.0;
-    let falloff = 100.0;
-
+    let inside = RidgedMulti::new_rand(24, 1.7, 1.9, 1.0, 0.75, 100.0);
     println!("Noise seed is {}", inside.get_seed());
 
-    let img_size = 512 as u32;
-    let mut imbuf = image::ImageBuf::new(img_size, img_size);
+    let img_size = 512_u32;
+    let mut imbuf = image::GrayImage::new(img_size, img_size);
+
+    let center = (img_size as f64) / 2.0;
+    let mut ngen = RadialMask::new(inside, 0.0, (img_size as f64) / 3.0, 30.0);
+    ngen.set_center([center, center, 0.0, 0.0]);
 
-    let radius: f64 = (img_size as f64) / 3.0;
-    let center: f64 = (img_size as f64) / 2.0;
-    
-    let start = precise_time_s();
-    for x in range(0, img_size) {
-        for y in range(0, img_size) {
-            let xx = x as f64;
-            let yy = y as f64;
-            let n = circle(radius, center, center, falloff,
-                            &mut inside, &mut outside,
-                            xx, yy);
+    let start = Instant::now();
+    for x in 0..img_size {
+        for y in 0..img_size {
+            let n = ngen.get_value2d(x as f64, y as f64);
             let col = (n * 255.0) as u8;
-            let pixel = image::Luma(col);
+            let pixel = image::Luma([col]);
             imbuf.put_pixel(x, y, pixel);
         }
     }
-    let end = precise_time_s();
+    let elapsed = start.elapsed();
 
-    let fout = File::create(&Path::new("circle.png")).unwrap();
-    let _ = image::ImageLuma8(imbuf).save(fout, image::PNG);
+    imbuf.save("circle.png").unwrap();
     println!("circle.png saved");
-    println!("generated {} points in {} ms", img_size*img_size, (end-start)*1000.0);
+    println!("generated {} points in {} ms", img_size*img_size, elapsed.as_secs_f64()*1000.0);
 }
\ No newline at end of file
@@ -1,36 +1,36 @@
 // example.rs
 
-extern crate noise;
-extern crate image;
-extern crate time;
-
 use noise::gen::NoiseGen;
 use noise::gen::fbm::FBM;
-use image::GenericImage;
-use std::io::File;
-use time::precise_time_s;
+use std::time::Instant;
 
 fn main() {
-    let mut ngen = FBM::new_rand(24, 0.5, 2.5, 100.0);
+    let ngen = FBM::builder()
+        .octaves(24)
+        .persistence(0.5)
+        .lacunarity(2.5)
+        .zoom(100.0)
+        .build()
+        .unwrap();
 
     println!("Noise seed is {}", ngen.get_seed());
     
-    let img_size = 512 as u32;
-    let mut imbuf = image::ImageBuf::new(img_size, img_size);
+    let img_size = 512_u32;
+    let mut imbuf = image::GrayImage::new(img_size, img_size);
     
-    let start = precise_time_s();
-    for x in range(0, img_size) {
-        for y in range(0, img_size) {
-            let n = ngen.get_value2d((x as f64), (y as f64));
-            let col = (n * 255.0) as u8;
-            let pixel = image::Luma(col);
-            imbuf.put_pixel(x, y, pixel);
-        }
+    let start = Instant::now();
+    let width = img_size as usize;
+    let mut noise_map = vec![0.0; width * width];
+    ngen.fill_2d((0.0, 0.0), 1.0, width, width, &mut noise_map);
+    let elapsed = start.elapsed();
+
+    for (x, y, pixel) in imbuf.enumerate_pixels_mut() {
+        let n = noise_map[(y as usize) * width + (x as usize)];
+        let col = (n * 255.0) as u8;
+        *pixel = image::Luma([col]);
     }
-    let end = precise_time_s();
 
-    let fout = File::create(&Path::new("fbm.png")).unwrap();
-    let _ = image::ImageLuma8(imbuf).save(fout, image::PNG);
+    imbuf.save("fbm.png").unwrap();
     println!("fbm.png saved");
-    println!("generated {} points in {} ms", img_size*img_size, (end-start)*1000.0);
+    println!("generated {} points in {} ms", img_size*img_size, elapsed.as_secs_f64()*1000.0);
 }
@@ -0,0 +1,34 @@
+// example.rs
+
+use noise::gen::NoiseGen;
+use noise::gen::hybridmulti::HybridMulti;
+use std::time::Instant;
+
+fn main() {
+    let ngen = HybridMulti::builder()
+        .octaves(12)
+        .zoom(200.0)
+        .build()
+        .unwrap();
+
+    println!("Noise seed is {}", ngen.get_seed());
+    
+    let img_size = 512_u32;
+    let mut imbuf = image::GrayImage::new(img_size, img_size);
+    
+    let start = Instant::now();
+    let width = img_size as usize;
+    let mut noise_map = vec![0.0; width * width];
+    ngen.fill_2d((0.0, 0.0), 1.0, width, width, &mut noise_map);
+    let elapsed = start.elapsed();
+
+    for (x, y, pixel) in imbuf.enumerate_pixels_mut() {
+        let n = noise_map[(y as usize) * width + (x as usize)];
+        let col = (n * 255.0) as u8;
+        *pixel = image::Luma([col]);
+    }
+
+    imbuf.save("hybrid.png").unwrap();
+    println!("hybrid.png saved");
+    println!("generated {} points in {} ms", img_size*img_size, elapsed.as_secs_f64()*1000.0);
+}
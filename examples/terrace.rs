@@ -0,0 +1,32 @@
+// terrace.rs
+
+use noise::gen::NoiseGen;
+use noise::gen::fbm::FBM;
+use noise::gen::modifier::Terrace;
+use std::time::Instant;
+
+fn main() {
+    let fbm = FBM::new_rand(6, 0.5, 2.5, 175.0);
+    println!("Noise seed is {}", fbm.get_seed());
+
+    // Flat topped mesas, with points spread over the range FBM mostly covers
+    let mut ngen = Terrace::new(fbm, &[0.0, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 1.0]).unwrap();
+    ngen.set_inverted(true);
+
+    let img_size = 512_u32;
+    let mut imbuf = image::GrayImage::new(img_size, img_size);
+
+    let start = Instant::now();
+    for x in 0..img_size {
+        for y in 0..img_size {
+            let n = ngen.get_value2d(x as f64, y as f64);
+            let col = (n * 255.0) as u8;
+            imbuf.put_pixel(x, y, image::Luma([col]));
+        }
+    }
+    let elapsed = start.elapsed();
+
+    imbuf.save("terrace.png").unwrap();
+    println!("terrace.png saved");
+    println!("generated {} points in {} ms", img_size*img_size, elapsed.as_secs_f64()*1000.0);
+}
@@ -0,0 +1,30 @@
+// example.rs
+
+use noise::gen::NoiseGen;
+use noise::gen::value::{Value, Curve};
+use std::time::Instant;
+
+fn main() {
+    let mut ngen = Value::new_rand(20.0);
+    ngen.set_curve(Curve::Cubic);
+
+    println!("Noise seed is {}", ngen.get_seed());
+    
+    let img_size = 512_u32;
+    let mut imbuf = image::GrayImage::new(img_size, img_size);
+
+    let start = Instant::now();
+    for x in 0..img_size {
+        for y in 0..img_size {
+            let n = ngen.get_value2d(x as f64, y as f64);
+            let col = (((n+1.0)/2.0) * 255.0) as u8;
+            let pixel = image::Luma([col]);
+            imbuf.put_pixel(x, y, pixel);
+        }
+    }
+    let elapsed = start.elapsed();
+
+    imbuf.save("value.png").unwrap();
+    println!("value.png saved");
+    println!("generated {} points in {} ms", img_size*img_size, elapsed.as_secs_f64()*1000.0);
+}
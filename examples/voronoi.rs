@@ -1,38 +1,37 @@
 // example.rs
 
-extern crate noise;
-extern crate image;
-extern crate time;
-
 use noise::gen::NoiseGen;
-use noise::gen::voronoi::Voronoi;
+use noise::gen::voronoi::{Voronoi, Output, Metric};
 use noise::utils::bound;
-use image::GenericImage;
-use std::io::File;
-use time::precise_time_s;
+use std::time::Instant;
 
 fn main() {
-    let mut ngen = Voronoi::new_rand(15.0);
+    let ngen = Voronoi::builder()
+        .zoom(15.0)
+        .jitter(1.0)
+        .output(Output::CellValue)
+        .metric(Metric::Euclidean)
+        .build()
+        .unwrap();
 
     println!("Noise seed is {}", ngen.get_seed());
     
-    let img_size = 512 as u32;
-    let mut imbuf = image::ImageBuf::new(img_size, img_size);
+    let img_size = 512_u32;
+    let mut imbuf = image::GrayImage::new(img_size, img_size);
     
-    let start = precise_time_s();
-    for x in range(0, img_size) {
-        for y in range(0, img_size) {
+    let start = Instant::now();
+    for x in 0..img_size {
+        for y in 0..img_size {
             let n = ngen.get_value2d(x as f64, y as f64);
             let nn = bound(n, 0.0, 1.0, -1.0, 1.0);
             let col = (nn * 255.0) as u8;
-            let pixel = image::Luma(col);
+            let pixel = image::Luma([col]);
             imbuf.put_pixel(x, y, pixel);
         }
     }
-    let end = precise_time_s();
+    let elapsed = start.elapsed();
 
-    let fout = File::create(&Path::new("voronoi.png")).unwrap();
-    let _ = image::ImageLuma8(imbuf).save(fout, image::PNG);
-    println!("voronoi.png saved")
-    println!("generated {} points in {} ms", img_size*img_size, (end-start)*1000.0);
+    imbuf.save("voronoi.png").unwrap();
+    println!("voronoi.png saved");
+    println!("generated {} points in {} ms", img_size*img_size, elapsed.as_secs_f64()*1000.0);
 }
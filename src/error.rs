@@ -0,0 +1,98 @@
+// src/error.rs
+
+use std::error::Error;
+use std::fmt;
+
+// Returned by the generator builders when a setting would give flat or NaN
+// noise. Each variant carries the rejected value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BuildError {
+    // octaves must be at least 1
+    Octaves(i32),
+    // zoom must be finite and non-zero
+    Zoom(f64),
+    // lacunarity must be finite and greater than 0
+    Lacunarity(f64),
+    // Voronoi jitter must be in [0,1]
+    Jitter(f64),
+    // the Minkowski exponent must be finite and at least 1
+    Minkowski(f64),
+    // Voronoi border width must be finite and greater than 0
+    BorderWidth(f64),
+    // Terrace needs at least 2 control points, carries how many it was given
+    TerraceCount(usize),
+    // Terrace control points must be finite and increasing, carries the index
+    // and value of the first one that isn't
+    TerracePoint(usize, f64),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::Octaves(n) => write!(f, "octaves must be at least 1, got {}", n),
+            BuildError::Zoom(z) => write!(f, "zoom must be finite and non-zero, got {}", z),
+            BuildError::Lacunarity(l) => write!(f, "lacunarity must be finite and greater than 0, got {}", l),
+            BuildError::Jitter(j) => write!(f, "jitter must be between 0 and 1, got {}", j),
+            BuildError::Minkowski(p) => write!(f, "Minkowski exponent must be finite and at least 1, got {}", p),
+            BuildError::BorderWidth(w) => write!(f, "border width must be finite and greater than 0, got {}", w),
+            BuildError::TerraceCount(n) => write!(f, "terrace needs at least 2 control points, got {}", n),
+            BuildError::TerracePoint(i, p) => write!(f, "terrace control points must be finite and increasing, got {} at index {}", p, i),
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+pub(crate) fn check_octaves(octaves: i32) -> Result<(), BuildError> {
+    if octaves < 1 {
+        return Err(BuildError::Octaves(octaves));
+    }
+    Ok(())
+}
+
+pub(crate) fn check_zoom(zoom: f64) -> Result<(), BuildError> {
+    if zoom == 0.0 || !zoom.is_finite() {
+        return Err(BuildError::Zoom(zoom));
+    }
+    Ok(())
+}
+
+pub(crate) fn check_lacunarity(lacunarity: f64) -> Result<(), BuildError> {
+    if lacunarity <= 0.0 || !lacunarity.is_finite() {
+        return Err(BuildError::Lacunarity(lacunarity));
+    }
+    Ok(())
+}
+
+pub(crate) fn check_jitter(jitter: f64) -> Result<(), BuildError> {
+    if !(0.0..=1.0).contains(&jitter) {
+        return Err(BuildError::Jitter(jitter));
+    }
+    Ok(())
+}
+
+pub(crate) fn check_minkowski(p: f64) -> Result<(), BuildError> {
+    if p < 1.0 || !p.is_finite() {
+        return Err(BuildError::Minkowski(p));
+    }
+    Ok(())
+}
+
+pub(crate) fn check_border_width(width: f64) -> Result<(), BuildError> {
+    if width <= 0.0 || !width.is_finite() {
+        return Err(BuildError::BorderWidth(width));
+    }
+    Ok(())
+}
+
+pub(crate) fn check_terrace_points(points: &[f64]) -> Result<(), BuildError> {
+    if points.len() < 2 {
+        return Err(BuildError::TerraceCount(points.len()));
+    }
+    for (i, &p) in points.iter().enumerate() {
+        if !p.is_finite() || (i > 0 && p <= points[i - 1]) {
+            return Err(BuildError::TerracePoint(i, p));
+        }
+    }
+    Ok(())
+}
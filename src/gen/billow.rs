@@ -1,87 +1,321 @@
 
-use gen::NoiseGen;
-use gen::simplex::Simplex;
-use std::num;
-
-pub struct Billow {
-    simp: Simplex,
-    octaves: int,
-    persitence: f64,
-    gain: f64,
+use crate::gen::{NoiseGen, octave_offsets};
+use crate::gen::simplex::Simplex;
+use crate::error::{BuildError, check_octaves, check_zoom, check_lacunarity};
+
+pub struct Billow<S = Simplex> {
+    source: S,
+    octaves: i32,
+    persistence: f64,
+    lacunarity: f64,
+    octave_seed: u32,
+    offsets: Vec<[f64; 4]>,
     max_val: f64
 }
 
 impl Billow {
-    pub fn new_rand(octaves: int, persitence: f64, gain: f64, zoom: f64) -> Billow {
-        Billow {simp: Simplex::new_rand(zoom),
-                octaves: octaves,
-                persitence: persitence,
-                gain: gain,
-                max_val: Billow::calc_max(octaves, persitence)
-            }
+    pub fn new_rand(octaves: i32, persistence: f64, lacunarity: f64, zoom: f64) -> Billow {
+        let mut ngen = Billow::from_source(Simplex::new_rand(zoom), octaves, persistence, lacunarity);
+        ngen.set_octave_seed(ngen.get_seed());
+        ngen
+    }
+
+    pub fn from_seed(seed:u32, octaves: i32, persistence: f64, lacunarity: f64, zoom: f64) -> Billow {
+        let mut ngen = Billow::from_source(Simplex::from_seed(seed, zoom), octaves, persistence, lacunarity);
+        ngen.set_octave_seed(ngen.get_seed());
+        ngen
+    }
+
+    pub fn get_seed(&self) -> u32 {
+        self.source.get_seed()
     }
 
-    pub fn from_seed(seed:u32, octaves: int, persitence: f64, gain: f64, zoom: f64) -> Billow {
-        Billow {simp: Simplex::from_seed(seed, zoom),
-                octaves: octaves,
-                persitence: persitence,
-                gain: gain,
-                max_val: Billow::calc_max(octaves, persitence)
+    pub fn set_zoom(&mut self, zoom: f64) -> Result<(), BuildError> {
+        check_zoom(zoom)?;
+        self.source.set_zoom(zoom);
+        Ok(())
+    }
+
+    pub fn get_zoom(&self) -> f64 {
+        self.source.get_zoom()
+    }
+}
+
+impl<S: NoiseGen> Billow<S> {
+    // Builds octaves of an existing generator. The source is expected to give
+    // values in [-1,1] like Simplex, otherwise the output won't fill [0,1]
+    pub fn from_source(source: S, octaves: i32, persistence: f64, lacunarity: f64) -> Billow<S> {
+        Billow {source,
+                octaves,
+                persistence,
+                lacunarity,
+                octave_seed: 0,
+                offsets: octave_offsets(0, octaves.max(0) as usize),
+                max_val: Self::calc_max(octaves, persistence)
             }
     }
 
-    pub fn get_seed(&mut self) -> u32 {
-        self.simp.get_seed()
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    // Nothing here is cached from the source, so it's safe to change in place
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    pub fn set_octaves(&mut self, octaves: i32) -> Result<(), BuildError> {
+        check_octaves(octaves)?;
+        self.octaves = octaves;
+        self.offsets = octave_offsets(self.octave_seed, octaves as usize);
+        self.max_val = Self::calc_max(octaves, self.persistence);
+        Ok(())
+    }
+
+    pub fn get_octaves(&self) -> i32 {
+        self.octaves
+    }
+
+    pub fn set_persistence(&mut self, persistence: f64) {
+        self.persistence = persistence;
+        self.max_val = Self::calc_max(self.octaves, persistence);
+    }
+
+    pub fn get_persistence(&self) -> f64 {
+        self.persistence
+    }
+
+    // lacunarity is the frequency multiplier between octaves
+    pub fn set_lacunarity(&mut self, lacunarity: f64) -> Result<(), BuildError> {
+        check_lacunarity(lacunarity)?;
+        self.lacunarity = lacunarity;
+        Ok(())
+    }
+
+    pub fn get_lacunarity(&self) -> f64 {
+        self.lacunarity
+    }
+
+    // Octaves after the first are moved by offsets drawn from this seed, see
+    // octave_offsets in gen/mod.rs. The Simplex constructors use the simplex
+    // seed, from_source starts at 0.
+    pub fn set_octave_seed(&mut self, seed: u32) {
+        self.octave_seed = seed;
+        self.offsets = octave_offsets(seed, self.offsets.len());
+    }
+
+    pub fn get_octave_seed(&self) -> u32 {
+        self.octave_seed
     }
 
     // static function for calculating the max/min values the noise can have
     // used to bound the noise to [-1,1]
-    // If the octaves or persitence ever change, max_val must be recalculated
-    fn calc_max(octaves: int, persitence: f64) -> f64 {
+    // set_octaves and set_persistence keep max_val up to date
+    fn calc_max(octaves: i32, persistence: f64) -> f64 {
         let mut a = 1.0;
         let mut n = 0.0;
-        for _ in range(0, octaves) {
+        for _ in 0..octaves {
             n += a;
-            a *= persitence;
+            a *= persistence;
         }
 
         n
     }
 }
 
-impl NoiseGen for Billow {
-    fn get_value2d(&mut self, x: f64, y: f64) -> f64 {
+impl<S: NoiseGen> NoiseGen for Billow<S> {
+    fn get_value1d(&self, x: f64) -> f64 {
+        let mut n = 0.0;
+        let mut signal;
+        let mut f = 1.0;
+        let mut amp = 1.0;
+
+        for o in self.offsets.iter() {
+            signal = self.source.get_value1d(x*f + o[0]);
+            signal = signal.abs();
+            n += signal * amp;
+            f *= self.lacunarity;
+            amp *= self.persistence;
+        }
+        // scale into [0,1]
+        n / self.max_val
+    }
+
+    fn get_value2d(&self, x: f64, y: f64) -> f64 {
         let mut n = 0.0;
         let mut signal;
         let mut f = 1.0;
         let mut amp = 1.0;
 
-        for _ in range(0, self.octaves) {
-            signal = self.simp.get_value2d(x*f, y*f);
-            signal = num::abs(signal);
+        for o in self.offsets.iter() {
+            signal = self.source.get_value2d(x*f + o[0], y*f + o[1]);
+            signal = signal.abs();
             n += signal * amp;
-            f *= self.gain;
-            amp *= self.persitence;
+            f *= self.lacunarity;
+            amp *= self.persistence;
         }
         // scale into [0,1]
         n / self.max_val
     }
 
-    fn get_value3d(&mut self, x: f64, y: f64, z: f64) -> f64 {
+    fn get_value3d(&self, x: f64, y: f64, z: f64) -> f64 {
         let mut n = 0.0;
         let mut signal;
         let mut f = 1.0;
         let mut amp = 1.0;
 
-        for _ in range(0, self.octaves) {
-            signal = self.simp.get_value3d(x*f, y*f, z*f);
-            signal = num::abs(signal);
+        for o in self.offsets.iter() {
+            signal = self.source.get_value3d(x*f + o[0], y*f + o[1], z*f + o[2]);
+            signal = signal.abs();
             n += signal * amp;
-            f *= self.gain;
-            amp *= self.persitence;
+            f *= self.lacunarity;
+            amp *= self.persistence;
         }
         // scale into [0,1]
         n / self.max_val
     }
+
+    fn get_value4d(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+        let mut n = 0.0;
+        let mut signal;
+        let mut f = 1.0;
+        let mut amp = 1.0;
+
+        for o in self.offsets.iter() {
+            signal = self.source.get_value4d(x*f + o[0], y*f + o[1], z*f + o[2], w*f + o[3]);
+            signal = signal.abs();
+            n += signal * amp;
+            f *= self.lacunarity;
+            amp *= self.persistence;
+        }
+        // scale into [0,1]
+        n / self.max_val
+    }
+
+    fn fill_2d(&self, origin: (f64, f64), step: f64, width: usize, height: usize, out: &mut [f64]) {
+        let len = width * height;
+        assert!(out.len() >= len, "fill_2d: buffer is smaller than width * height");
+        let out = &mut out[..len];
+
+        // Sum a whole octave at a time so the source's fill can reuse its row work
+        let mut octave = vec![0.0; len];
+        let mut f = 1.0;
+        let mut amp = 1.0;
+
+        out.fill(0.0);
+        for o in self.offsets.iter() {
+            self.source.fill_2d((origin.0*f + o[0], origin.1*f + o[1]), step*f, width, height, &mut octave);
+            for (n, signal) in out.iter_mut().zip(octave.iter()) {
+                *n += signal.abs() * amp;
+            }
+            f *= self.lacunarity;
+            amp *= self.persistence;
+        }
+        // scale into [0,1]
+        for n in out.iter_mut() {
+            *n /= self.max_val;
+        }
+    }
+
+    fn fill_3d(&self, origin: (f64, f64, f64), step: f64, width: usize, height: usize, depth: usize, out: &mut [f64]) {
+        let len = width * height * depth;
+        assert!(out.len() >= len, "fill_3d: buffer is smaller than width * height * depth");
+        let out = &mut out[..len];
+
+        let mut octave = vec![0.0; len];
+        let mut f = 1.0;
+        let mut amp = 1.0;
+
+        out.fill(0.0);
+        for o in self.offsets.iter() {
+            self.source.fill_3d((origin.0*f + o[0], origin.1*f + o[1], origin.2*f + o[2]), step*f, width, height, depth, &mut octave);
+            for (n, signal) in out.iter_mut().zip(octave.iter()) {
+                *n += signal.abs() * amp;
+            }
+            f *= self.lacunarity;
+            amp *= self.persistence;
+        }
+        // scale into [0,1]
+        for n in out.iter_mut() {
+            *n /= self.max_val;
+        }
+    }
 }
 
+// Named settings for Billow, checked when built. Defaults to 6 octaves,
+// persistence 0.5, lacunarity 2.0, zoom 1.0 and a random seed.
+//
+//     let ngen = Billow::builder().seed(7).octaves(8).zoom(100.0).build()?;
+#[derive(Clone, Debug)]
+pub struct BillowBuilder {
+    seed: Option<u32>,
+    octaves: i32,
+    persistence: f64,
+    lacunarity: f64,
+    zoom: f64,
+}
+
+impl Billow {
+    pub fn builder() -> BillowBuilder {
+        BillowBuilder::default()
+    }
+}
+
+impl Default for BillowBuilder {
+    fn default() -> BillowBuilder {
+        BillowBuilder {
+            seed: None,
+            octaves: 6,
+            persistence: 0.5,
+            lacunarity: 2.0,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl BillowBuilder {
+    pub fn seed(mut self, seed: u32) -> BillowBuilder {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn octaves(mut self, octaves: i32) -> BillowBuilder {
+        self.octaves = octaves;
+        self
+    }
+
+    pub fn persistence(mut self, persistence: f64) -> BillowBuilder {
+        self.persistence = persistence;
+        self
+    }
+
+    pub fn lacunarity(mut self, lacunarity: f64) -> BillowBuilder {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    pub fn zoom(mut self, zoom: f64) -> BillowBuilder {
+        self.zoom = zoom;
+        self
+    }
+
+    pub fn build(self) -> Result<Billow, BuildError> {
+        check_zoom(self.zoom)?;
+        let simp = match self.seed {
+            Some(seed) => Simplex::from_seed(seed, self.zoom),
+            None => Simplex::new_rand(self.zoom),
+        };
+        let seed = simp.get_seed();
+        self.seed(seed).build_with_source(simp)
+    }
+
+    // Builds octaves of source instead of Simplex. zoom is left to the source,
+    // and the seed, if any, only seeds the octave offsets.
+    pub fn build_with_source<S: NoiseGen>(self, source: S) -> Result<Billow<S>, BuildError> {
+        check_octaves(self.octaves)?;
+        check_lacunarity(self.lacunarity)?;
+        let mut ngen = Billow::from_source(source, self.octaves, self.persistence, self.lacunarity);
+        ngen.set_octave_seed(self.seed.unwrap_or(0));
+        Ok(ngen)
+    }
+}
@@ -0,0 +1,212 @@
+// combine.rs
+
+// Generators made from the values of other generators. Any NoiseGen can be a
+// source, including a constant f64, a reference or a Box<dyn NoiseGen>. The
+// generators in this crate also build these with +, - and *, so
+//
+//     let ngen = fbm * 0.5 + ridged;
+//
+// is Add<Multiply<FBM, f64>, RidgedMulti>. Negating one gives an Invert.
+
+use std::ops;
+use crate::gen::NoiseGen;
+use crate::gen::simplex::Simplex;
+use crate::gen::perlin::Perlin;
+use crate::gen::value::Value;
+use crate::gen::voronoi::Voronoi;
+use crate::gen::fbm::FBM;
+use crate::gen::billow::Billow;
+use crate::gen::ridgedmulti::RidgedMulti;
+use crate::gen::hybridmulti::HybridMulti;
+use crate::gen::heteroterrain::HeteroTerrain;
+use crate::gen::erosion::Erosion;
+use crate::gen::select::Select;
+use crate::gen::radialmask::RadialMask;
+use crate::gen::modifier::{ScaleBias, Abs, Invert, Clamp, Exponent, Remap, Terrace};
+use crate::utils::lerp;
+
+// A generator applying op to the values of lhs and rhs at the same point
+macro_rules! binary {
+    ($name:ident, |$a:ident, $b:ident| $op:expr) => {
+        pub struct $name<A, B> {
+            lhs: A,
+            rhs: B,
+        }
+
+        impl<A: NoiseGen, B: NoiseGen> $name<A, B> {
+            pub fn new(lhs: A, rhs: B) -> $name<A, B> {
+                $name { lhs, rhs }
+            }
+
+            pub fn lhs(&self) -> &A {
+                &self.lhs
+            }
+
+            pub fn lhs_mut(&mut self) -> &mut A {
+                &mut self.lhs
+            }
+
+            pub fn rhs(&self) -> &B {
+                &self.rhs
+            }
+
+            pub fn rhs_mut(&mut self) -> &mut B {
+                &mut self.rhs
+            }
+        }
+
+        impl<A: NoiseGen, B: NoiseGen> NoiseGen for $name<A, B> {
+            fn get_value1d(&self, x: f64) -> f64 {
+                let ($a, $b) = (self.lhs.get_value1d(x), self.rhs.get_value1d(x));
+                $op
+            }
+
+            fn get_value2d(&self, x: f64, y: f64) -> f64 {
+                let ($a, $b) = (self.lhs.get_value2d(x, y), self.rhs.get_value2d(x, y));
+                $op
+            }
+
+            fn get_value3d(&self, x: f64, y: f64, z: f64) -> f64 {
+                let ($a, $b) = (self.lhs.get_value3d(x, y, z), self.rhs.get_value3d(x, y, z));
+                $op
+            }
+
+            fn get_value4d(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+                let ($a, $b) = (self.lhs.get_value4d(x, y, z, w), self.rhs.get_value4d(x, y, z, w));
+                $op
+            }
+        }
+    };
+}
+
+binary!(Add, |a, b| a + b);
+binary!(Subtract, |a, b| a - b);
+binary!(Multiply, |a, b| a * b);
+binary!(Min, |a, b| a.min(b));
+binary!(Max, |a, b| a.max(b));
+// lhs raised to the power of rhs. Negative lhs with a fractional rhs is NaN,
+// so sources in [-1,1] usually want shifting into [0,1] first.
+binary!(Power, |a, b| a.powf(b));
+
+// low and high mixed by the value of control, low where it's 0 and high
+// where it's 1. Unlike Select both sources are always sampled, and controls
+// outside [0,1] extrapolate past them.
+pub struct Blend<L, H, C> {
+    low: L,
+    high: H,
+    control: C,
+}
+
+impl<L: NoiseGen, H: NoiseGen, C: NoiseGen> Blend<L, H, C> {
+    pub fn new(low: L, high: H, control: C) -> Blend<L, H, C> {
+        Blend { low, high, control }
+    }
+
+    pub fn low(&self) -> &L {
+        &self.low
+    }
+
+    pub fn low_mut(&mut self) -> &mut L {
+        &mut self.low
+    }
+
+    pub fn high(&self) -> &H {
+        &self.high
+    }
+
+    pub fn high_mut(&mut self) -> &mut H {
+        &mut self.high
+    }
+
+    pub fn control(&self) -> &C {
+        &self.control
+    }
+
+    pub fn control_mut(&mut self) -> &mut C {
+        &mut self.control
+    }
+}
+
+impl<L: NoiseGen, H: NoiseGen, C: NoiseGen> NoiseGen for Blend<L, H, C> {
+    fn get_value1d(&self, x: f64) -> f64 {
+        lerp(self.low.get_value1d(x), self.high.get_value1d(x), self.control.get_value1d(x))
+    }
+
+    fn get_value2d(&self, x: f64, y: f64) -> f64 {
+        lerp(self.low.get_value2d(x, y), self.high.get_value2d(x, y), self.control.get_value2d(x, y))
+    }
+
+    fn get_value3d(&self, x: f64, y: f64, z: f64) -> f64 {
+        lerp(self.low.get_value3d(x, y, z), self.high.get_value3d(x, y, z), self.control.get_value3d(x, y, z))
+    }
+
+    fn get_value4d(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+        lerp(self.low.get_value4d(x, y, z, w), self.high.get_value4d(x, y, z, w), self.control.get_value4d(x, y, z, w))
+    }
+}
+
+// +, - and * for a generator type, with any NoiseGen on the right, and unary
+// -. std::ops can't be implemented for every NoiseGen at once, so each type
+// is listed.
+macro_rules! impl_ops {
+    ($name:ident $(<$($g:ident),*>)?) => {
+        impl<$($($g,)*)? R: NoiseGen> ops::Add<R> for $name$(<$($g),*>)? {
+            type Output = Add<Self, R>;
+
+            fn add(self, rhs: R) -> Add<Self, R> {
+                Add { lhs: self, rhs }
+            }
+        }
+
+        impl<$($($g,)*)? R: NoiseGen> ops::Sub<R> for $name$(<$($g),*>)? {
+            type Output = Subtract<Self, R>;
+
+            fn sub(self, rhs: R) -> Subtract<Self, R> {
+                Subtract { lhs: self, rhs }
+            }
+        }
+
+        impl<$($($g,)*)? R: NoiseGen> ops::Mul<R> for $name$(<$($g),*>)? {
+            type Output = Multiply<Self, R>;
+
+            fn mul(self, rhs: R) -> Multiply<Self, R> {
+                Multiply { lhs: self, rhs }
+            }
+        }
+
+        impl<$($($g),*)?> ops::Neg for $name$(<$($g),*>)? {
+            type Output = Invert<Self>;
+
+            fn neg(self) -> Invert<Self> {
+                Invert::new(self)
+            }
+        }
+    };
+}
+
+impl_ops!(Simplex);
+impl_ops!(Perlin);
+impl_ops!(Value);
+impl_ops!(Voronoi);
+impl_ops!(FBM<S>);
+impl_ops!(Billow<S>);
+impl_ops!(RidgedMulti<S>);
+impl_ops!(HybridMulti<S>);
+impl_ops!(HeteroTerrain<S>);
+impl_ops!(Erosion);
+impl_ops!(Select<C, L, H>);
+impl_ops!(RadialMask<I, O>);
+impl_ops!(Add<A, B>);
+impl_ops!(Subtract<A, B>);
+impl_ops!(Multiply<A, B>);
+impl_ops!(Min<A, B>);
+impl_ops!(Max<A, B>);
+impl_ops!(Power<A, B>);
+impl_ops!(Blend<L, H, C>);
+impl_ops!(ScaleBias<S>);
+impl_ops!(Abs<S>);
+impl_ops!(Invert<S>);
+impl_ops!(Clamp<S>);
+impl_ops!(Exponent<S>);
+impl_ops!(Remap<S>);
+impl_ops!(Terrace<S>);
@@ -0,0 +1,270 @@
+// erosion.rs
+
+// fBm where each octave is damped by the slope of the octaves before it,
+// after Inigo Quilez's "derivative" terrain. Steep areas get less detail, which
+// reads as erosion. The gradient is summed in lattice units so the damping
+// doesn't change with zoom.
+
+use crate::gen::{NoiseGen, NoiseDeriv, octave_offsets};
+use crate::gen::simplex::Simplex;
+use crate::error::{BuildError, check_octaves, check_zoom, check_lacunarity};
+use crate::utils::bound;
+
+pub struct Erosion {
+    // zoom is applied here rather than in simp, so its derivatives are in lattice units
+    simp: Simplex,
+    zoom: f64,
+    octaves: i32,
+    persistence: f64,
+    lacunarity: f64,
+    octave_seed: u32,
+    offsets: Vec<[f64; 4]>,
+    max_val: f64
+}
+
+impl Erosion {
+    pub fn new_rand(octaves: i32, persistence: f64, lacunarity: f64, zoom: f64) -> Erosion {
+        Erosion::from_simplex(Simplex::new_rand(1.0), octaves, persistence, lacunarity, zoom)
+    }
+
+    pub fn from_seed(seed: u32, octaves: i32, persistence: f64, lacunarity: f64, zoom: f64) -> Erosion {
+        Erosion::from_simplex(Simplex::from_seed(seed, 1.0), octaves, persistence, lacunarity, zoom)
+    }
+
+    fn from_simplex(simp: Simplex, octaves: i32, persistence: f64, lacunarity: f64, zoom: f64) -> Erosion {
+        let seed = simp.get_seed();
+        Erosion {
+            simp,
+            zoom: 1.0/zoom,
+            octaves,
+            persistence,
+            lacunarity,
+            octave_seed: seed,
+            offsets: octave_offsets(seed, octaves.max(0) as usize),
+            max_val: Erosion::calc_max(octaves, persistence)
+        }
+    }
+
+    pub fn get_seed(&self) -> u32 {
+        self.simp.get_seed()
+    }
+
+    pub fn set_zoom(&mut self, zoom: f64) -> Result<(), BuildError> {
+        check_zoom(zoom)?;
+        self.zoom = 1.0/zoom;
+        Ok(())
+    }
+
+    pub fn get_zoom(&self) -> f64 {
+        1.0/self.zoom
+    }
+
+    pub fn set_octaves(&mut self, octaves: i32) -> Result<(), BuildError> {
+        check_octaves(octaves)?;
+        self.octaves = octaves;
+        self.offsets = octave_offsets(self.octave_seed, octaves as usize);
+        self.max_val = Erosion::calc_max(octaves, self.persistence);
+        Ok(())
+    }
+
+    pub fn get_octaves(&self) -> i32 {
+        self.octaves
+    }
+
+    pub fn set_persistence(&mut self, persistence: f64) {
+        self.persistence = persistence;
+        self.max_val = Erosion::calc_max(self.octaves, persistence);
+    }
+
+    pub fn get_persistence(&self) -> f64 {
+        self.persistence
+    }
+
+    pub fn set_lacunarity(&mut self, lacunarity: f64) -> Result<(), BuildError> {
+        check_lacunarity(lacunarity)?;
+        self.lacunarity = lacunarity;
+        Ok(())
+    }
+
+    pub fn get_lacunarity(&self) -> f64 {
+        self.lacunarity
+    }
+
+    // Octaves after the first are moved by offsets drawn from this seed, see
+    // octave_offsets in gen/mod.rs. Starts as the simplex seed.
+    pub fn set_octave_seed(&mut self, seed: u32) {
+        self.octave_seed = seed;
+        self.offsets = octave_offsets(seed, self.offsets.len());
+    }
+
+    pub fn get_octave_seed(&self) -> u32 {
+        self.octave_seed
+    }
+
+    // The damping is at most 1, so this is the same bound as FBM
+    fn calc_max(octaves: i32, persistence: f64) -> f64 {
+        let mut a = 1.0;
+        let mut n = 0.0;
+        for _ in 0..octaves {
+            n += a;
+            a *= persistence;
+        }
+
+        n
+    }
+}
+
+impl NoiseGen for Erosion {
+    fn get_value1d(&self, x: f64) -> f64 {
+        let x = x * self.zoom;
+        let mut n = 0.0;
+        let mut f = 1.0;
+        let mut amp = 1.0;
+        let mut dx = 0.0;
+
+        for o in self.offsets.iter() {
+            let (v, vx) = self.simp.get_value1d_deriv(x*f + o[0]);
+            dx += vx;
+            n += amp * v / (1.0 + dx*dx);
+            f *= self.lacunarity;
+            amp *= self.persistence;
+        }
+
+        bound(n, 0.0, 1.0, -self.max_val, self.max_val)
+    }
+
+    fn get_value2d(&self, x: f64, y: f64) -> f64 {
+        let x = x * self.zoom;
+        let y = y * self.zoom;
+        let mut n = 0.0;
+        let mut f = 1.0;
+        let mut amp = 1.0;
+        let (mut dx, mut dy) = (0.0, 0.0);
+
+        for o in self.offsets.iter() {
+            let (v, vx, vy) = self.simp.get_value2d_deriv(x*f + o[0], y*f + o[1]);
+            dx += vx;
+            dy += vy;
+            n += amp * v / (1.0 + dx*dx + dy*dy);
+            f *= self.lacunarity;
+            amp *= self.persistence;
+        }
+
+        bound(n, 0.0, 1.0, -self.max_val, self.max_val)
+    }
+
+    fn get_value3d(&self, x: f64, y: f64, z: f64) -> f64 {
+        let x = x * self.zoom;
+        let y = y * self.zoom;
+        let z = z * self.zoom;
+        let mut n = 0.0;
+        let mut f = 1.0;
+        let mut amp = 1.0;
+        let (mut dx, mut dy, mut dz) = (0.0, 0.0, 0.0);
+
+        for o in self.offsets.iter() {
+            let (v, vx, vy, vz) = self.simp.get_value3d_deriv(x*f + o[0], y*f + o[1], z*f + o[2]);
+            dx += vx;
+            dy += vy;
+            dz += vz;
+            n += amp * v / (1.0 + dx*dx + dy*dy + dz*dz);
+            f *= self.lacunarity;
+            amp *= self.persistence;
+        }
+
+        bound(n, 0.0, 1.0, -self.max_val, self.max_val)
+    }
+
+    fn get_value4d(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+        let x = x * self.zoom;
+        let y = y * self.zoom;
+        let z = z * self.zoom;
+        let w = w * self.zoom;
+        let mut n = 0.0;
+        let mut f = 1.0;
+        let mut amp = 1.0;
+        let (mut dx, mut dy, mut dz, mut dw) = (0.0, 0.0, 0.0, 0.0);
+
+        for o in self.offsets.iter() {
+            let (v, vx, vy, vz, vw) = self.simp.get_value4d_deriv(x*f + o[0], y*f + o[1], z*f + o[2], w*f + o[3]);
+            dx += vx;
+            dy += vy;
+            dz += vz;
+            dw += vw;
+            n += amp * v / (1.0 + dx*dx + dy*dy + dz*dz + dw*dw);
+            f *= self.lacunarity;
+            amp *= self.persistence;
+        }
+
+        bound(n, 0.0, 1.0, -self.max_val, self.max_val)
+    }
+}
+
+// Named settings for Erosion, checked when built. Defaults to 6 octaves,
+// persistence 0.5, lacunarity 2.0, zoom 1.0 and a random seed.
+//
+//     let ngen = Erosion::builder().seed(7).octaves(8).zoom(100.0).build()?;
+#[derive(Clone, Debug)]
+pub struct ErosionBuilder {
+    seed: Option<u32>,
+    octaves: i32,
+    persistence: f64,
+    lacunarity: f64,
+    zoom: f64,
+}
+
+impl Erosion {
+    pub fn builder() -> ErosionBuilder {
+        ErosionBuilder::default()
+    }
+}
+
+impl Default for ErosionBuilder {
+    fn default() -> ErosionBuilder {
+        ErosionBuilder {
+            seed: None,
+            octaves: 6,
+            persistence: 0.5,
+            lacunarity: 2.0,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl ErosionBuilder {
+    pub fn seed(mut self, seed: u32) -> ErosionBuilder {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn octaves(mut self, octaves: i32) -> ErosionBuilder {
+        self.octaves = octaves;
+        self
+    }
+
+    pub fn persistence(mut self, persistence: f64) -> ErosionBuilder {
+        self.persistence = persistence;
+        self
+    }
+
+    pub fn lacunarity(mut self, lacunarity: f64) -> ErosionBuilder {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    pub fn zoom(mut self, zoom: f64) -> ErosionBuilder {
+        self.zoom = zoom;
+        self
+    }
+
+    pub fn build(self) -> Result<Erosion, BuildError> {
+        check_octaves(self.octaves)?;
+        check_lacunarity(self.lacunarity)?;
+        check_zoom(self.zoom)?;
+        let simp = match self.seed {
+            Some(seed) => Simplex::from_seed(seed, 1.0),
+            None => Simplex::new_rand(1.0),
+        };
+        Ok(Erosion::from_simplex(simp, self.octaves, self.persistence, self.lacunarity, self.zoom))
+    }
+}
@@ -1,81 +1,394 @@
 
-use gen::NoiseGen;
-use gen::simplex::Simplex;
-use utils::bound;
-
-pub struct FBM {
-    simp: Simplex,
-    octaves: int,
-    persitence: f64,
-    gain: f64,
+use crate::gen::{NoiseGen, NoiseDeriv, octave_offsets};
+use crate::gen::simplex::Simplex;
+use crate::error::{BuildError, check_octaves, check_zoom, check_lacunarity};
+use crate::utils::bound;
+
+pub struct FBM<S = Simplex> {
+    source: S,
+    octaves: i32,
+    persistence: f64,
+    lacunarity: f64,
+    octave_seed: u32,
+    offsets: Vec<[f64; 4]>,
     max_val: f64
 }
 
 impl FBM {
-    pub fn new_rand(octaves: int, persitence: f64, gain: f64, zoom: f64) -> FBM {
-        FBM {   simp: Simplex::new_rand(zoom),
-                octaves: octaves,
-                persitence: persitence,
-                gain: gain,
-                max_val: FBM::calc_max(octaves, persitence)
-            }
+    pub fn new_rand(octaves: i32, persistence: f64, lacunarity: f64, zoom: f64) -> FBM {
+        let mut ngen = FBM::from_source(Simplex::new_rand(zoom), octaves, persistence, lacunarity);
+        ngen.set_octave_seed(ngen.get_seed());
+        ngen
+    }
+
+    pub fn from_seed(seed:u32, octaves: i32, persistence: f64, lacunarity: f64, zoom: f64) -> FBM {
+        let mut ngen = FBM::from_source(Simplex::from_seed(seed, zoom), octaves, persistence, lacunarity);
+        ngen.set_octave_seed(ngen.get_seed());
+        ngen
+    }
+
+    pub fn get_seed(&self) -> u32 {
+        self.source.get_seed()
+    }
+
+    pub fn set_zoom(&mut self, zoom: f64) -> Result<(), BuildError> {
+        check_zoom(zoom)?;
+        self.source.set_zoom(zoom);
+        Ok(())
     }
 
-    pub fn from_seed(seed:u32, octaves: int, persitence: f64, gain: f64, zoom: f64) -> FBM {
-        FBM {   simp: Simplex::from_seed(seed, zoom),
-                octaves: octaves,
-                persitence: persitence,
-                gain: gain,
-                max_val: FBM::calc_max(octaves, persitence)
+    pub fn get_zoom(&self) -> f64 {
+        self.source.get_zoom()
+    }
+}
+
+impl<S: NoiseGen> FBM<S> {
+    // Builds octaves of an existing generator. The source is expected to give
+    // values in [-1,1] like Simplex, otherwise the output won't fill [0,1]
+    pub fn from_source(source: S, octaves: i32, persistence: f64, lacunarity: f64) -> FBM<S> {
+        FBM {   source,
+                octaves,
+                persistence,
+                lacunarity,
+                octave_seed: 0,
+                offsets: octave_offsets(0, octaves.max(0) as usize),
+                max_val: Self::calc_max(octaves, persistence)
             }
     }
 
-    pub fn get_seed(&mut self) -> u32 {
-        self.simp.get_seed()
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    // Nothing here is cached from the source, so it's safe to change in place
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    // set_octaves, set_lacunarity and set_zoom reject what the builder does,
+    // leaving the generator as it was
+    pub fn set_octaves(&mut self, octaves: i32) -> Result<(), BuildError> {
+        check_octaves(octaves)?;
+        self.octaves = octaves;
+        self.offsets = octave_offsets(self.octave_seed, octaves as usize);
+        self.max_val = Self::calc_max(octaves, self.persistence);
+        Ok(())
+    }
+
+    pub fn get_octaves(&self) -> i32 {
+        self.octaves
+    }
+
+    pub fn set_persistence(&mut self, persistence: f64) {
+        self.persistence = persistence;
+        self.max_val = Self::calc_max(self.octaves, persistence);
+    }
+
+    pub fn get_persistence(&self) -> f64 {
+        self.persistence
+    }
+
+    // lacunarity is the frequency multiplier between octaves
+    pub fn set_lacunarity(&mut self, lacunarity: f64) -> Result<(), BuildError> {
+        check_lacunarity(lacunarity)?;
+        self.lacunarity = lacunarity;
+        Ok(())
+    }
+
+    pub fn get_lacunarity(&self) -> f64 {
+        self.lacunarity
+    }
+
+    // Octaves after the first are moved by offsets drawn from this seed, see
+    // octave_offsets in gen/mod.rs. The Simplex constructors use the simplex
+    // seed, from_source starts at 0.
+    pub fn set_octave_seed(&mut self, seed: u32) {
+        self.octave_seed = seed;
+        self.offsets = octave_offsets(seed, self.offsets.len());
+    }
+
+    pub fn get_octave_seed(&self) -> u32 {
+        self.octave_seed
     }
 
     // static function for calculating the max/min values the noise can have
     // used to bound the noise to [-1,1]
-    // If the octaves or persitence ever change, max_val must be recalculated
-    fn calc_max(octaves: int, persitence: f64) -> f64 {
+    // set_octaves and set_persistence keep max_val up to date
+    fn calc_max(octaves: i32, persistence: f64) -> f64 {
         let mut a = 1.0;
         let mut n = 0.0;
-        for _ in range(0, octaves) {
+        for _ in 0..octaves {
             n += a;
-            a *= persitence;
+            a *= persistence;
         }
 
         n
     }
 }
 
-impl NoiseGen for FBM {
-    fn get_value2d(&mut self, x: f64, y: f64) -> f64 {
+impl<S: NoiseGen> NoiseGen for FBM<S> {
+    fn get_value1d(&self, x: f64) -> f64 {
+        let mut n = 0.0;
+        let mut f = 1.0;
+        let mut amp = 1.0;
+
+        for o in self.offsets.iter() {
+            n += amp * self.source.get_value1d(x*f + o[0]);
+            f *= self.lacunarity;
+            amp *= self.persistence;
+        }
+
+        bound(n, 0.0, 1.0, -self.max_val, self.max_val)
+    }
+
+    fn get_value2d(&self, x: f64, y: f64) -> f64 {
+        let mut n = 0.0;
+        let mut f = 1.0;
+        let mut amp = 1.0;
+
+        for o in self.offsets.iter() {
+            n += amp * self.source.get_value2d(x*f + o[0], y*f + o[1]);
+            f *= self.lacunarity;
+            amp *= self.persistence;
+        }
+
+        bound(n, 0.0, 1.0, -self.max_val, self.max_val)
+    }
+
+    fn get_value3d(&self, x: f64, y: f64, z: f64) -> f64 {
         let mut n = 0.0;
         let mut f = 1.0;
         let mut amp = 1.0;
 
-        for _ in range(0, self.octaves) {
-            n += amp * self.simp.get_value2d(x*f, y*f);
-            f *= self.gain;
-            amp *= self.persitence;
+        for o in self.offsets.iter() {
+            n += amp * self.source.get_value3d(x*f + o[0], y*f + o[1], z*f + o[2]);
+            f *= self.lacunarity;
+            amp *= self.persistence;
         }
 
         bound(n, 0.0, 1.0, -self.max_val, self.max_val)
     }
 
-    fn get_value3d(&mut self, x: f64, y: f64, z: f64) -> f64 {
+    fn get_value4d(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
         let mut n = 0.0;
         let mut f = 1.0;
         let mut amp = 1.0;
 
-        for _ in range(0, self.octaves) {
-            n += amp * self.simp.get_value3d(x*f, y*f, z*f);
-            f *= self.gain;
-            amp *= self.persitence;
+        for o in self.offsets.iter() {
+            n += amp * self.source.get_value4d(x*f + o[0], y*f + o[1], z*f + o[2], w*f + o[3]);
+            f *= self.lacunarity;
+            amp *= self.persistence;
         }
 
         bound(n, 0.0, 1.0, -self.max_val, self.max_val)
     }
+
+    fn fill_2d(&self, origin: (f64, f64), step: f64, width: usize, height: usize, out: &mut [f64]) {
+        let len = width * height;
+        assert!(out.len() >= len, "fill_2d: buffer is smaller than width * height");
+        let out = &mut out[..len];
+
+        // Sum a whole octave at a time so the source's fill can reuse its row work
+        let mut octave = vec![0.0; len];
+        let mut f = 1.0;
+        let mut amp = 1.0;
+
+        out.fill(0.0);
+        for o in self.offsets.iter() {
+            self.source.fill_2d((origin.0*f + o[0], origin.1*f + o[1]), step*f, width, height, &mut octave);
+            for (n, v) in out.iter_mut().zip(octave.iter()) {
+                *n += amp * v;
+            }
+            f *= self.lacunarity;
+            amp *= self.persistence;
+        }
+
+        for n in out.iter_mut() {
+            *n = bound(*n, 0.0, 1.0, -self.max_val, self.max_val);
+        }
+    }
+
+    fn fill_3d(&self, origin: (f64, f64, f64), step: f64, width: usize, height: usize, depth: usize, out: &mut [f64]) {
+        let len = width * height * depth;
+        assert!(out.len() >= len, "fill_3d: buffer is smaller than width * height * depth");
+        let out = &mut out[..len];
+
+        let mut octave = vec![0.0; len];
+        let mut f = 1.0;
+        let mut amp = 1.0;
+
+        out.fill(0.0);
+        for o in self.offsets.iter() {
+            self.source.fill_3d((origin.0*f + o[0], origin.1*f + o[1], origin.2*f + o[2]), step*f, width, height, depth, &mut octave);
+            for (n, v) in out.iter_mut().zip(octave.iter()) {
+                *n += amp * v;
+            }
+            f *= self.lacunarity;
+            amp *= self.persistence;
+        }
+
+        for n in out.iter_mut() {
+            *n = bound(*n, 0.0, 1.0, -self.max_val, self.max_val);
+        }
+    }
+}
+
+// Octave i samples the source at x*f + o, so by the chain rule its gradient is
+// scaled by f as well as amp. bound is linear, so the sum just gets its slope.
+impl<S: NoiseDeriv> NoiseDeriv for FBM<S> {
+    fn get_value1d_deriv(&self, x: f64) -> (f64, f64) {
+        let mut n = 0.0;
+        let mut dx = 0.0;
+        let mut f = 1.0;
+        let mut amp = 1.0;
+
+        for o in self.offsets.iter() {
+            let (v, vx) = self.source.get_value1d_deriv(x*f + o[0]);
+            n += amp * v;
+            dx += amp * f * vx;
+            f *= self.lacunarity;
+            amp *= self.persistence;
+        }
+
+        let s = 0.5 / self.max_val;
+        (bound(n, 0.0, 1.0, -self.max_val, self.max_val), dx * s)
+    }
+
+    fn get_value2d_deriv(&self, x: f64, y: f64) -> (f64, f64, f64) {
+        let mut n = 0.0;
+        let (mut dx, mut dy) = (0.0, 0.0);
+        let mut f = 1.0;
+        let mut amp = 1.0;
+
+        for o in self.offsets.iter() {
+            let (v, vx, vy) = self.source.get_value2d_deriv(x*f + o[0], y*f + o[1]);
+            n += amp * v;
+            dx += amp * f * vx;
+            dy += amp * f * vy;
+            f *= self.lacunarity;
+            amp *= self.persistence;
+        }
+
+        let s = 0.5 / self.max_val;
+        (bound(n, 0.0, 1.0, -self.max_val, self.max_val), dx * s, dy * s)
+    }
+
+    fn get_value3d_deriv(&self, x: f64, y: f64, z: f64) -> (f64, f64, f64, f64) {
+        let mut n = 0.0;
+        let (mut dx, mut dy, mut dz) = (0.0, 0.0, 0.0);
+        let mut f = 1.0;
+        let mut amp = 1.0;
+
+        for o in self.offsets.iter() {
+            let (v, vx, vy, vz) = self.source.get_value3d_deriv(x*f + o[0], y*f + o[1], z*f + o[2]);
+            n += amp * v;
+            dx += amp * f * vx;
+            dy += amp * f * vy;
+            dz += amp * f * vz;
+            f *= self.lacunarity;
+            amp *= self.persistence;
+        }
+
+        let s = 0.5 / self.max_val;
+        (bound(n, 0.0, 1.0, -self.max_val, self.max_val), dx * s, dy * s, dz * s)
+    }
+
+    fn get_value4d_deriv(&self, x: f64, y: f64, z: f64, w: f64) -> (f64, f64, f64, f64, f64) {
+        let mut n = 0.0;
+        let (mut dx, mut dy, mut dz, mut dw) = (0.0, 0.0, 0.0, 0.0);
+        let mut f = 1.0;
+        let mut amp = 1.0;
+
+        for o in self.offsets.iter() {
+            let (v, vx, vy, vz, vw) = self.source.get_value4d_deriv(x*f + o[0], y*f + o[1], z*f + o[2], w*f + o[3]);
+            n += amp * v;
+            dx += amp * f * vx;
+            dy += amp * f * vy;
+            dz += amp * f * vz;
+            dw += amp * f * vw;
+            f *= self.lacunarity;
+            amp *= self.persistence;
+        }
+
+        let s = 0.5 / self.max_val;
+        (bound(n, 0.0, 1.0, -self.max_val, self.max_val), dx * s, dy * s, dz * s, dw * s)
+    }
+}
+
+// Named settings for FBM, checked when built. Defaults to 6 octaves,
+// persistence 0.5, lacunarity 2.0, zoom 1.0 and a random seed.
+//
+//     let ngen = FBM::builder().seed(7).octaves(8).zoom(100.0).build()?;
+#[derive(Clone, Debug)]
+pub struct FBMBuilder {
+    seed: Option<u32>,
+    octaves: i32,
+    persistence: f64,
+    lacunarity: f64,
+    zoom: f64,
+}
+
+impl FBM {
+    pub fn builder() -> FBMBuilder {
+        FBMBuilder::default()
+    }
 }
 
+impl Default for FBMBuilder {
+    fn default() -> FBMBuilder {
+        FBMBuilder {
+            seed: None,
+            octaves: 6,
+            persistence: 0.5,
+            lacunarity: 2.0,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl FBMBuilder {
+    pub fn seed(mut self, seed: u32) -> FBMBuilder {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn octaves(mut self, octaves: i32) -> FBMBuilder {
+        self.octaves = octaves;
+        self
+    }
+
+    pub fn persistence(mut self, persistence: f64) -> FBMBuilder {
+        self.persistence = persistence;
+        self
+    }
+
+    pub fn lacunarity(mut self, lacunarity: f64) -> FBMBuilder {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    pub fn zoom(mut self, zoom: f64) -> FBMBuilder {
+        self.zoom = zoom;
+        self
+    }
+
+    pub fn build(self) -> Result<FBM, BuildError> {
+        check_zoom(self.zoom)?;
+        let simp = match self.seed {
+            Some(seed) => Simplex::from_seed(seed, self.zoom),
+            None => Simplex::new_rand(self.zoom),
+        };
+        let seed = simp.get_seed();
+        self.seed(seed).build_with_source(simp)
+    }
+
+    // Builds octaves of source instead of Simplex. zoom is left to the source,
+    // and the seed, if any, only seeds the octave offsets.
+    pub fn build_with_source<S: NoiseGen>(self, source: S) -> Result<FBM<S>, BuildError> {
+        check_octaves(self.octaves)?;
+        check_lacunarity(self.lacunarity)?;
+        let mut ngen = FBM::from_source(source, self.octaves, self.persistence, self.lacunarity);
+        ngen.set_octave_seed(self.seed.unwrap_or(0));
+        Ok(ngen)
+    }
+}
@@ -0,0 +1,358 @@
+// heteroterrain.rs
+
+// Musgrave's heterogeneous terrain. Each octave is scaled by the height so
+// far, so valleys stay smooth while higher ground gets rough detail.
+
+use crate::gen::{NoiseGen, octave_offsets, calc_freqs, interval_mul};
+use crate::gen::simplex::Simplex;
+use crate::error::{BuildError, check_octaves, check_zoom, check_lacunarity};
+use crate::utils::bound;
+
+pub struct HeteroTerrain<S = Simplex> {
+    source: S,
+    octaves: i32,
+    lacunarity: f64,
+    offset: f64,
+    h: f64,
+    frequencies: Vec<f64>,
+    octave_seed: u32,
+    offsets: Vec<[f64; 4]>,
+    min_val: f64,
+    max_val: f64
+}
+
+impl HeteroTerrain {
+    pub fn new_rand(octaves: i32, lac: f64, offset: f64, h: f64, zoom: f64) -> HeteroTerrain {
+        let mut ngen = HeteroTerrain::from_source(Simplex::new_rand(zoom), octaves, lac, offset, h);
+        ngen.set_octave_seed(ngen.get_seed());
+        ngen
+    }
+
+    pub fn from_seed(seed: u32, octaves: i32, lac: f64, offset: f64, h: f64, zoom: f64) -> HeteroTerrain {
+        let mut ngen = HeteroTerrain::from_source(Simplex::from_seed(seed, zoom), octaves, lac, offset, h);
+        ngen.set_octave_seed(ngen.get_seed());
+        ngen
+    }
+
+    pub fn get_seed(&self) -> u32 {
+        self.source.get_seed()
+    }
+
+    pub fn set_zoom(&mut self, zoom: f64) -> Result<(), BuildError> {
+        check_zoom(zoom)?;
+        self.source.set_zoom(zoom);
+        Ok(())
+    }
+
+    pub fn get_zoom(&self) -> f64 {
+        self.source.get_zoom()
+    }
+}
+
+impl<S: NoiseGen> HeteroTerrain<S> {
+    // Builds terrain from octaves of an existing generator, which should give
+    // values in [-1,1] like Simplex. Always takes at least one octave.
+    pub fn from_source(source: S, octaves: i32, lac: f64, offset: f64, h: f64) -> HeteroTerrain<S> {
+        let mut ngen = HeteroTerrain {
+            source,
+            octaves,
+            lacunarity: lac,
+            offset,
+            h,
+            frequencies: Vec::new(),
+            octave_seed: 0,
+            offsets: octave_offsets(0, octaves.max(1) as usize),
+            min_val: 0.0,
+            max_val: 0.0
+        };
+        ngen.update_freqs();
+        ngen
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    // Nothing here is cached from the source, so it's safe to change in place
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    pub fn set_octaves(&mut self, octaves: i32) -> Result<(), BuildError> {
+        check_octaves(octaves)?;
+        self.octaves = octaves;
+        self.offsets = octave_offsets(self.octave_seed, octaves as usize);
+        self.update_freqs();
+        Ok(())
+    }
+
+    pub fn get_octaves(&self) -> i32 {
+        self.octaves
+    }
+
+    pub fn set_lacunarity(&mut self, lac: f64) -> Result<(), BuildError> {
+        check_lacunarity(lac)?;
+        self.lacunarity = lac;
+        self.update_freqs();
+        Ok(())
+    }
+
+    pub fn get_lacunarity(&self) -> f64 {
+        self.lacunarity
+    }
+
+    pub fn set_offset(&mut self, offset: f64) {
+        self.offset = offset;
+        self.update_freqs();
+    }
+
+    pub fn get_offset(&self) -> f64 {
+        self.offset
+    }
+
+    pub fn set_h(&mut self, h: f64) {
+        self.h = h;
+        self.update_freqs();
+    }
+
+    pub fn get_h(&self) -> f64 {
+        self.h
+    }
+
+    // Octaves after the first are moved by offsets drawn from this seed, see
+    // octave_offsets in gen/mod.rs. The Simplex constructors use the simplex
+    // seed, from_source starts at 0.
+    pub fn set_octave_seed(&mut self, seed: u32) {
+        self.octave_seed = seed;
+        self.offsets = octave_offsets(seed, self.offsets.len());
+    }
+
+    pub fn get_octave_seed(&self) -> u32 {
+        self.octave_seed
+    }
+
+    fn update_freqs(&mut self) {
+        self.frequencies = calc_freqs(self.octaves.max(1), self.lacunarity, self.h);
+        let (min, max) = Self::calc_bounds(self.offset, &self.frequencies);
+        self.min_val = min;
+        self.max_val = max;
+    }
+
+    // Each octave scales the value by 1 + signal, and with a negative offset
+    // the signal can flip the sign, so carry the range of the value through
+    // every octave for source values in [-1,1].
+    fn calc_bounds(offset: f64, freqs: &[f64]) -> (f64, f64) {
+        let mut value = (offset - 1.0, offset + 1.0);
+        for &f in freqs[1..].iter() {
+            let scale = (1.0 + (offset - 1.0) * f, 1.0 + (offset + 1.0) * f);
+            value = interval_mul(value, scale);
+        }
+
+        value
+    }
+}
+
+impl<S: NoiseGen> NoiseGen for HeteroTerrain<S> {
+    fn get_value1d(&self, x: f64) -> f64 {
+        let mut xx = x;
+        let mut value = self.source.get_value1d(x) + self.offset;
+
+        for (i, o) in self.offsets.iter().enumerate().skip(1) {
+            xx *= self.lacunarity;
+            let signal = (self.source.get_value1d(xx + o[0]) + self.offset) * self.frequencies[i];
+            value += signal * value;
+        }
+
+        bound(value, 0.0, 1.0, self.min_val, self.max_val)
+    }
+
+    fn get_value2d(&self, x: f64, y: f64) -> f64 {
+        let mut xx = x;
+        let mut yy = y;
+        let mut value = self.source.get_value2d(x, y) + self.offset;
+
+        for (i, o) in self.offsets.iter().enumerate().skip(1) {
+            xx *= self.lacunarity;
+            yy *= self.lacunarity;
+            let signal = (self.source.get_value2d(xx + o[0], yy + o[1]) + self.offset) * self.frequencies[i];
+            value += signal * value;
+        }
+
+        bound(value, 0.0, 1.0, self.min_val, self.max_val)
+    }
+
+    fn get_value3d(&self, x: f64, y: f64, z: f64) -> f64 {
+        let mut xx = x;
+        let mut yy = y;
+        let mut zz = z;
+        let mut value = self.source.get_value3d(x, y, z) + self.offset;
+
+        for (i, o) in self.offsets.iter().enumerate().skip(1) {
+            xx *= self.lacunarity;
+            yy *= self.lacunarity;
+            zz *= self.lacunarity;
+            let signal = (self.source.get_value3d(xx + o[0], yy + o[1], zz + o[2]) + self.offset) * self.frequencies[i];
+            value += signal * value;
+        }
+
+        bound(value, 0.0, 1.0, self.min_val, self.max_val)
+    }
+
+    fn get_value4d(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+        let mut xx = x;
+        let mut yy = y;
+        let mut zz = z;
+        let mut ww = w;
+        let mut value = self.source.get_value4d(x, y, z, w) + self.offset;
+
+        for (i, o) in self.offsets.iter().enumerate().skip(1) {
+            xx *= self.lacunarity;
+            yy *= self.lacunarity;
+            zz *= self.lacunarity;
+            ww *= self.lacunarity;
+            let signal = (self.source.get_value4d(xx + o[0], yy + o[1], zz + o[2], ww + o[3]) + self.offset) * self.frequencies[i];
+            value += signal * value;
+        }
+
+        bound(value, 0.0, 1.0, self.min_val, self.max_val)
+    }
+
+    fn fill_2d(&self, origin: (f64, f64), step: f64, width: usize, height: usize, out: &mut [f64]) {
+        let len = width * height;
+        assert!(out.len() >= len, "fill_2d: buffer is smaller than width * height");
+        let out = &mut out[..len];
+
+        // Run a whole octave at a time so the source's fill can reuse its row work
+        let mut octave = vec![0.0; len];
+        self.source.fill_2d(origin, step, width, height, &mut octave);
+        for (n, &v) in out.iter_mut().zip(octave.iter()) {
+            *n = v + self.offset;
+        }
+
+        let mut f = 1.0;
+        for (i, o) in self.offsets.iter().enumerate().skip(1) {
+            f *= self.lacunarity;
+            self.source.fill_2d((origin.0*f + o[0], origin.1*f + o[1]), step*f, width, height, &mut octave);
+            for (n, &v) in out.iter_mut().zip(octave.iter()) {
+                let signal = (v + self.offset) * self.frequencies[i];
+                *n += signal * *n;
+            }
+        }
+
+        for n in out.iter_mut() {
+            *n = bound(*n, 0.0, 1.0, self.min_val, self.max_val);
+        }
+    }
+
+    fn fill_3d(&self, origin: (f64, f64, f64), step: f64, width: usize, height: usize, depth: usize, out: &mut [f64]) {
+        let len = width * height * depth;
+        assert!(out.len() >= len, "fill_3d: buffer is smaller than width * height * depth");
+        let out = &mut out[..len];
+
+        let mut octave = vec![0.0; len];
+        self.source.fill_3d(origin, step, width, height, depth, &mut octave);
+        for (n, &v) in out.iter_mut().zip(octave.iter()) {
+            *n = v + self.offset;
+        }
+
+        let mut f = 1.0;
+        for (i, o) in self.offsets.iter().enumerate().skip(1) {
+            f *= self.lacunarity;
+            self.source.fill_3d((origin.0*f + o[0], origin.1*f + o[1], origin.2*f + o[2]), step*f, width, height, depth, &mut octave);
+            for (n, &v) in out.iter_mut().zip(octave.iter()) {
+                let signal = (v + self.offset) * self.frequencies[i];
+                *n += signal * *n;
+            }
+        }
+
+        for n in out.iter_mut() {
+            *n = bound(*n, 0.0, 1.0, self.min_val, self.max_val);
+        }
+    }
+}
+
+// Named settings for HeteroTerrain, checked when built. Defaults to 6 octaves,
+// lacunarity 2.0, offset 0.7, H 1.0, zoom 1.0 and a random seed. Lower H lets
+// the octaves compound, which pushes most values into a narrow band.
+//
+//     let ngen = HeteroTerrain::builder().seed(7).octaves(8).zoom(100.0).build()?;
+#[derive(Clone, Debug)]
+pub struct HeteroTerrainBuilder {
+    seed: Option<u32>,
+    octaves: i32,
+    lacunarity: f64,
+    offset: f64,
+    h: f64,
+    zoom: f64,
+}
+
+impl HeteroTerrain {
+    pub fn builder() -> HeteroTerrainBuilder {
+        HeteroTerrainBuilder::default()
+    }
+}
+
+impl Default for HeteroTerrainBuilder {
+    fn default() -> HeteroTerrainBuilder {
+        HeteroTerrainBuilder {
+            seed: None,
+            octaves: 6,
+            lacunarity: 2.0,
+            offset: 0.7,
+            h: 1.0,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl HeteroTerrainBuilder {
+    pub fn seed(mut self, seed: u32) -> HeteroTerrainBuilder {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn octaves(mut self, octaves: i32) -> HeteroTerrainBuilder {
+        self.octaves = octaves;
+        self
+    }
+
+    pub fn lacunarity(mut self, lacunarity: f64) -> HeteroTerrainBuilder {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    pub fn offset(mut self, offset: f64) -> HeteroTerrainBuilder {
+        self.offset = offset;
+        self
+    }
+
+    pub fn h(mut self, h: f64) -> HeteroTerrainBuilder {
+        self.h = h;
+        self
+    }
+
+    pub fn zoom(mut self, zoom: f64) -> HeteroTerrainBuilder {
+        self.zoom = zoom;
+        self
+    }
+
+    pub fn build(self) -> Result<HeteroTerrain, BuildError> {
+        check_zoom(self.zoom)?;
+        let simp = match self.seed {
+            Some(seed) => Simplex::from_seed(seed, self.zoom),
+            None => Simplex::new_rand(self.zoom),
+        };
+        let seed = simp.get_seed();
+        self.seed(seed).build_with_source(simp)
+    }
+
+    // Builds terrain from octaves of source instead of Simplex. zoom is left to the
+    // source, and the seed, if any, only seeds the octave offsets.
+    pub fn build_with_source<S: NoiseGen>(self, source: S) -> Result<HeteroTerrain<S>, BuildError> {
+        check_octaves(self.octaves)?;
+        check_lacunarity(self.lacunarity)?;
+        let mut ngen = HeteroTerrain::from_source(source, self.octaves, self.lacunarity, self.offset, self.h);
+        ngen.set_octave_seed(self.seed.unwrap_or(0));
+        Ok(ngen)
+    }
+}
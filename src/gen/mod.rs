@@ -5,20 +5,208 @@ pub mod fbm;
 pub mod ridgedmulti;
 pub mod billow;
 pub mod voronoi;
+pub mod hybridmulti;
+pub mod heteroterrain;
+pub mod erosion;
+pub mod perlin;
+pub mod value;
+pub mod select;
+pub mod radialmask;
+pub mod combine;
+pub mod modifier;
+
+use crate::lcgrng::LCG;
 
 pub trait NoiseGen {
-    fn get_value2d(&mut self, x: f64, y: f64) -> f64;
-    fn get_value3d(&mut self, x: f64, y: f64, z: f64) -> f64;
+    fn get_value1d(&self, x: f64) -> f64;
+    fn get_value2d(&self, x: f64, y: f64) -> f64;
+    fn get_value3d(&self, x: f64, y: f64, z: f64) -> f64;
+    fn get_value4d(&self, x: f64, y: f64, z: f64, w: f64) -> f64;
+
+    // Fills out with a width x height grid of samples, row by row, starting
+    // at origin and spaced step apart along each axis
+    fn fill_2d(&self, origin: (f64, f64), step: f64, width: usize, height: usize, out: &mut [f64]) {
+        assert!(out.len() >= width * height, "fill_2d: buffer is smaller than width * height");
+        if width == 0 {
+            return;
+        }
+
+        for (j, row) in out.chunks_mut(width).take(height).enumerate() {
+            let y = origin.1 + (j as f64) * step;
+            for (i, n) in row.iter_mut().enumerate() {
+                *n = self.get_value2d(origin.0 + (i as f64) * step, y);
+            }
+        }
+    }
+
+    // Like fill_2d, with depth consecutive width x height slices
+    fn fill_3d(&self, origin: (f64, f64, f64), step: f64, width: usize, height: usize, depth: usize, out: &mut [f64]) {
+        assert!(out.len() >= width * height * depth, "fill_3d: buffer is smaller than width * height * depth");
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        for (k, slice) in out.chunks_mut(width * height).take(depth).enumerate() {
+            let z = origin.2 + (k as f64) * step;
+            for (j, row) in slice.chunks_mut(width).enumerate() {
+                let y = origin.1 + (j as f64) * step;
+                for (i, n) in row.iter_mut().enumerate() {
+                    *n = self.get_value3d(origin.0 + (i as f64) * step, y, z);
+                }
+            }
+        }
+    }
+}
+
+// Generators that can give their partial derivatives along with the value,
+// as (value, d/dx, d/dy, ...). Cheaper and more exact than sampling around a
+// point for normals and slopes.
+pub trait NoiseDeriv: NoiseGen {
+    fn get_value1d_deriv(&self, x: f64) -> (f64, f64);
+    fn get_value2d_deriv(&self, x: f64, y: f64) -> (f64, f64, f64);
+    fn get_value3d_deriv(&self, x: f64, y: f64, z: f64) -> (f64, f64, f64, f64);
+    fn get_value4d_deriv(&self, x: f64, y: f64, z: f64, w: f64) -> (f64, f64, f64, f64, f64);
+}
+
+// Shuffled 0..=255 for the lattice hashes of the gradient noises. Index it
+// with each coordinate & 0xFF in turn, adding the previous lookup.
+pub(crate) fn seeded_perm(seed: u32) -> [u8; 256] {
+    let mut rng = LCG::from_seed(seed);
+    let mut perm = [0; 256];
+
+    for (i, p) in perm.iter_mut().enumerate() {
+        *p = i as u8;
+    }
+    rng.shuffle(&mut perm);
+    perm
+}
+
+// Hashes a lattice point through perm, one coordinate at a time
+pub(crate) fn perm_hash(perm: &[u8; 256], lattice: &[i64]) -> u8 {
+    let mut h = 0;
+    for &l in lattice.iter() {
+        h = perm[((h as usize) + ((l & 0xFF) as usize)) & 0xFF];
+    }
+    h
+}
+
+// Coordinate offsets for the octaves of the fractal generators, so octaves
+// don't all share the source's lattice point at the origin. The first octave
+// isn't moved. Every later one takes four draws from an LCG seeded with seed,
+// one per axis in x, y, z, w order, each mapped from [0, 2^32) to [0, 1024).
+//
+// The offsets are added to the coordinates passed to the fractal, before the
+// source divides by its zoom, as a generic source's zoom can't be seen from
+// here. In the source's lattice that's at most 1024/zoom cells, so the
+// decorrelation weakens as zoom grows: by a zoom of around 1000 the octaves
+// move by about a cell and their features can still visibly line up. Sample
+// with a smaller zoom and scaled down coordinates if that shows.
+pub(crate) fn octave_offsets(seed: u32, count: usize) -> Vec<[f64; 4]> {
+    let mut rng = LCG::from_seed(seed);
+    let mut offsets = Vec::with_capacity(count);
+    for i in 0..count {
+        let mut o = [0.0; 4];
+        if i > 0 {
+            for a in o.iter_mut() {
+                *a = (rng.next_u32() as f64) * (1024.0 / 4294967296.0);
+            }
+        }
+        offsets.push(o);
+    }
+
+    offsets
+}
+
+// Spectral weights for Musgrave's multifractals, lacunarity^(-i*H) for octave i
+pub(crate) fn calc_freqs(octaves: i32, lacunarity: f64, h: f64) -> Vec<f64> {
+    let mut freqs: Vec<f64> = Vec::new();
+
+    for i in 0..octaves {
+        let f = lacunarity.powf((-i as f64) * h);
+        freqs.push(f);
+    }
+
+    freqs
+}
+
+// Product of two intervals, for bounding the multifractals whose octaves
+// multiply into each other
+pub(crate) fn interval_mul(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    let p = [a.0 * b.0, a.0 * b.1, a.1 * b.0, a.1 * b.1];
+    let lo = p.iter().cloned().fold(f64::INFINITY, f64::min);
+    let hi = p.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    (lo, hi)
+}
+
+// So a generator can be shared by reference, or picked at runtime as a
+// Box<dyn NoiseGen>. The fills are passed through to keep any batching.
+impl<T: NoiseGen + ?Sized> NoiseGen for &T {
+    fn get_value1d(&self, x: f64) -> f64 {
+        (**self).get_value1d(x)
+    }
+
+    fn get_value2d(&self, x: f64, y: f64) -> f64 {
+        (**self).get_value2d(x, y)
+    }
+
+    fn get_value3d(&self, x: f64, y: f64, z: f64) -> f64 {
+        (**self).get_value3d(x, y, z)
+    }
+
+    fn get_value4d(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+        (**self).get_value4d(x, y, z, w)
+    }
+
+    fn fill_2d(&self, origin: (f64, f64), step: f64, width: usize, height: usize, out: &mut [f64]) {
+        (**self).fill_2d(origin, step, width, height, out)
+    }
+
+    fn fill_3d(&self, origin: (f64, f64, f64), step: f64, width: usize, height: usize, depth: usize, out: &mut [f64]) {
+        (**self).fill_3d(origin, step, width, height, depth, out)
+    }
 }
 
+impl<T: NoiseGen + ?Sized> NoiseGen for Box<T> {
+    fn get_value1d(&self, x: f64) -> f64 {
+        (**self).get_value1d(x)
+    }
 
-#[allow(unused_variable)]
+    fn get_value2d(&self, x: f64, y: f64) -> f64 {
+        (**self).get_value2d(x, y)
+    }
+
+    fn get_value3d(&self, x: f64, y: f64, z: f64) -> f64 {
+        (**self).get_value3d(x, y, z)
+    }
+
+    fn get_value4d(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+        (**self).get_value4d(x, y, z, w)
+    }
+
+    fn fill_2d(&self, origin: (f64, f64), step: f64, width: usize, height: usize, out: &mut [f64]) {
+        (**self).fill_2d(origin, step, width, height, out)
+    }
+
+    fn fill_3d(&self, origin: (f64, f64, f64), step: f64, width: usize, height: usize, depth: usize, out: &mut [f64]) {
+        (**self).fill_3d(origin, step, width, height, depth, out)
+    }
+}
+
+#[allow(unused_variables)]
 impl NoiseGen for f64 {
-    fn get_value2d(&mut self, x: f64, y: f64) -> f64 {
+    fn get_value1d(&self, x: f64) -> f64 {
+        *self
+    }
+
+    fn get_value2d(&self, x: f64, y: f64) -> f64 {
+        *self
+    }
+
+    fn get_value3d(&self, x: f64, y: f64, z: f64) -> f64 {
         *self
     }
 
-    fn get_value3d(&mut self, x: f64, y: f64, z: f64) -> f64 {
+    fn get_value4d(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
         *self
     }
 }
@@ -0,0 +1,258 @@
+// modifier.rs
+
+// Generators that change the values of a single source, so post processing
+// can be part of a graph instead of being done after every sample.
+
+use crate::gen::NoiseGen;
+use crate::error::{BuildError, check_terrace_points};
+use crate::utils::{lerp, bound, clamp};
+
+// NoiseGen for a modifier, applying op to each value of its source, along
+// with the source accessors
+macro_rules! modifier {
+    ($name:ident, |$s:ident, $v:ident| $op:expr) => {
+        impl<S: NoiseGen> $name<S> {
+            pub fn source(&self) -> &S {
+                &self.source
+            }
+
+            pub fn source_mut(&mut self) -> &mut S {
+                &mut self.source
+            }
+        }
+
+        impl<S: NoiseGen> NoiseGen for $name<S> {
+            fn get_value1d(&self, x: f64) -> f64 {
+                let ($s, $v) = (self, self.source.get_value1d(x));
+                $op
+            }
+
+            fn get_value2d(&self, x: f64, y: f64) -> f64 {
+                let ($s, $v) = (self, self.source.get_value2d(x, y));
+                $op
+            }
+
+            fn get_value3d(&self, x: f64, y: f64, z: f64) -> f64 {
+                let ($s, $v) = (self, self.source.get_value3d(x, y, z));
+                $op
+            }
+
+            fn get_value4d(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+                let ($s, $v) = (self, self.source.get_value4d(x, y, z, w));
+                $op
+            }
+        }
+    };
+}
+
+// source * scale + bias
+pub struct ScaleBias<S> {
+    source: S,
+    scale: f64,
+    bias: f64,
+}
+
+impl<S: NoiseGen> ScaleBias<S> {
+    pub fn new(source: S, scale: f64, bias: f64) -> ScaleBias<S> {
+        ScaleBias { source, scale, bias }
+    }
+
+    pub fn set_scale(&mut self, scale: f64) {
+        self.scale = scale;
+    }
+
+    pub fn get_scale(&self) -> f64 {
+        self.scale
+    }
+
+    pub fn set_bias(&mut self, bias: f64) {
+        self.bias = bias;
+    }
+
+    pub fn get_bias(&self) -> f64 {
+        self.bias
+    }
+}
+
+modifier!(ScaleBias, |m, v| v * m.scale + m.bias);
+
+pub struct Abs<S> {
+    source: S,
+}
+
+impl<S: NoiseGen> Abs<S> {
+    pub fn new(source: S) -> Abs<S> {
+        Abs { source }
+    }
+}
+
+modifier!(Abs, |_m, v| v.abs());
+
+// The source negated. -ngen builds one for the generators in this crate.
+pub struct Invert<S> {
+    source: S,
+}
+
+// Unbounded so unary - can build one for any generator type
+impl<S> Invert<S> {
+    pub fn new(source: S) -> Invert<S> {
+        Invert { source }
+    }
+}
+
+modifier!(Invert, |_m, v| -v);
+
+// Limits the source to [low, high]
+pub struct Clamp<S> {
+    source: S,
+    low: f64,
+    high: f64,
+}
+
+impl<S: NoiseGen> Clamp<S> {
+    pub fn new(source: S, low: f64, high: f64) -> Clamp<S> {
+        Clamp { source, low, high }
+    }
+
+    pub fn set_bounds(&mut self, low: f64, high: f64) {
+        self.low = low;
+        self.high = high;
+    }
+
+    pub fn get_bounds(&self) -> (f64, f64) {
+        (self.low, self.high)
+    }
+}
+
+modifier!(Clamp, |m, v| clamp(v, m.low, m.high));
+
+// Raises the source to a power, keeping [-1,1] in [-1,1]. Values are moved
+// into [0,1] first so negatives don't give NaN, then moved back, as libnoise
+// does. Exponents above 1 flatten the low end and sharpen the peaks.
+pub struct Exponent<S> {
+    source: S,
+    exponent: f64,
+}
+
+impl<S: NoiseGen> Exponent<S> {
+    pub fn new(source: S, exponent: f64) -> Exponent<S> {
+        Exponent { source, exponent }
+    }
+
+    pub fn set_exponent(&mut self, exponent: f64) {
+        self.exponent = exponent;
+    }
+
+    pub fn get_exponent(&self) -> f64 {
+        self.exponent
+    }
+}
+
+modifier!(Exponent, |m, v| ((v + 1.0) * 0.5).abs().powf(m.exponent) * 2.0 - 1.0);
+
+// Maps the source linearly from one range to another, for example from the
+// [0,1] of the fractals to the [-1,1] Simplex gives. Values outside from end
+// up outside to, add a Clamp if that matters.
+pub struct Remap<S> {
+    source: S,
+    from: (f64, f64),
+    to: (f64, f64),
+}
+
+impl<S: NoiseGen> Remap<S> {
+    pub fn new(source: S, from: (f64, f64), to: (f64, f64)) -> Remap<S> {
+        Remap { source, from, to }
+    }
+
+    pub fn set_from(&mut self, from: (f64, f64)) {
+        self.from = from;
+    }
+
+    pub fn get_from(&self) -> (f64, f64) {
+        self.from
+    }
+
+    pub fn set_to(&mut self, to: (f64, f64)) {
+        self.to = to;
+    }
+
+    pub fn get_to(&self) -> (f64, f64) {
+        self.to
+    }
+}
+
+modifier!(Remap, |m, v| bound(v, m.to.0, m.to.1, m.from.0, m.from.1));
+
+// Flattens the source into terraces between increasing control points, for
+// mesas and paddy fields. Unlike utils::step the points are validated, and
+// values between them can be eased rather than cut.
+//
+// Within each pair of points the value is eased from the lower one, so
+// terraces are flat at the bottom and steep at the top. Inverted eases from
+// the upper point instead, flat at the top like a mesa. Values outside the
+// points are clamped to the first and last.
+pub struct Terrace<S> {
+    source: S,
+    points: Vec<f64>,
+    smooth: bool,
+    inverted: bool,
+}
+
+impl<S: NoiseGen> Terrace<S> {
+    // Defaults to smooth, not inverted terraces
+    pub fn new(source: S, points: &[f64]) -> Result<Terrace<S>, BuildError> {
+        check_terrace_points(points)?;
+        Ok(Terrace { source, points: points.to_vec(), smooth: true, inverted: false })
+    }
+
+    pub fn set_points(&mut self, points: &[f64]) -> Result<(), BuildError> {
+        check_terrace_points(points)?;
+        self.points = points.to_vec();
+        Ok(())
+    }
+
+    pub fn get_points(&self) -> &[f64] {
+        &self.points
+    }
+
+    // Quadratic easing between points when true, hard steps when false
+    pub fn set_smooth(&mut self, smooth: bool) {
+        self.smooth = smooth;
+    }
+
+    pub fn get_smooth(&self) -> bool {
+        self.smooth
+    }
+
+    pub fn set_inverted(&mut self, inverted: bool) {
+        self.inverted = inverted;
+    }
+
+    pub fn get_inverted(&self) -> bool {
+        self.inverted
+    }
+
+    fn terrace(&self, v: f64) -> f64 {
+        let points = &self.points;
+        // How many points are at or below v
+        let i = points.partition_point(|&p| p <= v);
+        if i == 0 {
+            return points[0];
+        }
+        if i == points.len() {
+            return points[i - 1];
+        }
+
+        let (low, high) = (points[i - 1], points[i]);
+        let t = (v - low) / (high - low);
+        // Hard steps floor t, which is in [0,1)
+        let ease = |t: f64| if self.smooth { t * t } else { t.floor() };
+        if self.inverted {
+            lerp(high, low, ease(1.0 - t))
+        } else {
+            lerp(low, high, ease(t))
+        }
+    }
+}
+
+modifier!(Terrace, |m, v| m.terrace(v));
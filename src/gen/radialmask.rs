@@ -0,0 +1,98 @@
+// radialmask.rs
+
+// Inside a radius of the center the output is inside, beyond it outside, and
+// within falloff either side of the radius the two are blended. That's an
+// interval in 1D, a circle in 2D, a sphere in 3D and a hypersphere in 4D.
+// Unlike utils::circle and utils::sphere the falloff is a plain distance, so
+// the blend is just as wide whatever the radius.
+
+use crate::gen::NoiseGen;
+use crate::utils::select_with;
+
+pub struct RadialMask<I, O> {
+    inside: I,
+    outside: O,
+    center: [f64; 4],
+    radius: f64,
+    falloff: f64,
+}
+
+impl<I: NoiseGen, O: NoiseGen> RadialMask<I, O> {
+    // Centered on the origin, see set_center
+    pub fn new(inside: I, outside: O, radius: f64, falloff: f64) -> RadialMask<I, O> {
+        RadialMask { inside, outside, center: [0.0; 4], radius, falloff }
+    }
+
+    pub fn inside(&self) -> &I {
+        &self.inside
+    }
+
+    pub fn inside_mut(&mut self) -> &mut I {
+        &mut self.inside
+    }
+
+    pub fn outside(&self) -> &O {
+        &self.outside
+    }
+
+    pub fn outside_mut(&mut self) -> &mut O {
+        &mut self.outside
+    }
+
+    // x, y, z, w. Lower dimensions only use the axes they have, so a circle
+    // is centered on (center[0], center[1]).
+    pub fn set_center(&mut self, center: [f64; 4]) {
+        self.center = center;
+    }
+
+    pub fn get_center(&self) -> [f64; 4] {
+        self.center
+    }
+
+    pub fn set_radius(&mut self, radius: f64) {
+        self.radius = radius;
+    }
+
+    pub fn get_radius(&self) -> f64 {
+        self.radius
+    }
+
+    // Half the width of the blend around the radius, 0 for a hard edge
+    pub fn set_falloff(&mut self, falloff: f64) {
+        self.falloff = falloff;
+    }
+
+    pub fn get_falloff(&self) -> f64 {
+        self.falloff
+    }
+
+    fn distance(&self, p: &[f64]) -> f64 {
+        p.iter().zip(self.center.iter()).map(|(a, c)| (a - c) * (a - c)).sum::<f64>().sqrt()
+    }
+}
+
+impl<I: NoiseGen, O: NoiseGen> NoiseGen for RadialMask<I, O> {
+    fn get_value1d(&self, x: f64) -> f64 {
+        select_with(self.distance(&[x]), self.radius, self.falloff,
+                    || self.inside.get_value1d(x),
+                    || self.outside.get_value1d(x))
+    }
+
+    fn get_value2d(&self, x: f64, y: f64) -> f64 {
+        select_with(self.distance(&[x, y]), self.radius, self.falloff,
+                    || self.inside.get_value2d(x, y),
+                    || self.outside.get_value2d(x, y))
+    }
+
+    fn get_value3d(&self, x: f64, y: f64, z: f64) -> f64 {
+        select_with(self.distance(&[x, y, z]), self.radius, self.falloff,
+                    || self.inside.get_value3d(x, y, z),
+                    || self.outside.get_value3d(x, y, z))
+    }
+
+    fn get_value4d(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+        select_with(self.distance(&[x, y, z, w]), self.radius, self.falloff,
+                    || self.inside.get_value4d(x, y, z, w),
+                    || self.outside.get_value4d(x, y, z, w))
+    }
+}
@@ -1,131 +1,417 @@
 // ridgedmulti.rs
 
-use gen::NoiseGen;
-use gen::simplex::Simplex;
-use utils::{clamp, bound};
-use std::num;
-
-pub struct RidgedMulti {
-    simp: Simplex,
-    octaves: int,
+use crate::gen::{NoiseGen, octave_offsets, calc_freqs};
+use crate::gen::simplex::Simplex;
+use crate::error::{BuildError, check_octaves, check_zoom, check_lacunarity};
+use crate::utils::{clamp, bound};
+
+pub struct RidgedMulti<S = Simplex> {
+    source: S,
+    octaves: i32,
     gain: f64,
     lacunarity: f64,
     offset: f64,
+    h: f64,
     frequencies: Vec<f64>,
+    octave_seed: u32,
+    offsets: Vec<[f64; 4]>,
     max_val: f64
 }
 
 impl RidgedMulti {
-    pub fn new_rand(octaves: int, gain: f64, lac: f64, offset: f64, h: f64, zoom: f64) -> RidgedMulti {
-        let freqs = RidgedMulti::calc_freqs(octaves, lac, h);
-        RidgedMulti {  
-            simp: Simplex::new_rand(zoom),
-            octaves: octaves,
-            gain: gain,
-            lacunarity: lac,
-            offset: offset,
-            frequencies: freqs.clone(),
-            max_val: RidgedMulti::calc_max(octaves, gain, offset, freqs)
-            }
+    pub fn new_rand(octaves: i32, gain: f64, lac: f64, offset: f64, h: f64, zoom: f64) -> RidgedMulti {
+        let mut ngen = RidgedMulti::from_source(Simplex::new_rand(zoom), octaves, gain, lac, offset, h);
+        ngen.set_octave_seed(ngen.get_seed());
+        ngen
+    }
+
+    pub fn from_seed(seed: u32, octaves: i32, gain: f64, lac: f64, offset: f64, h: f64, zoom: f64) -> RidgedMulti {
+        let mut ngen = RidgedMulti::from_source(Simplex::from_seed(seed, zoom), octaves, gain, lac, offset, h);
+        ngen.set_octave_seed(ngen.get_seed());
+        ngen
+    }
+
+    pub fn get_seed(&self) -> u32 {
+        self.source.get_seed()
     }
 
-    pub fn from_seed(seed: u32, octaves: int, gain: f64, lac: f64, offset: f64, h: f64, zoom: f64) -> RidgedMulti {
-        let freqs = RidgedMulti::calc_freqs(octaves, lac, h);
+    pub fn set_zoom(&mut self, zoom: f64) -> Result<(), BuildError> {
+        check_zoom(zoom)?;
+        self.source.set_zoom(zoom);
+        Ok(())
+    }
+
+    pub fn get_zoom(&self) -> f64 {
+        self.source.get_zoom()
+    }
+}
+
+impl<S: NoiseGen> RidgedMulti<S> {
+    // Builds ridged octaves of an existing generator. The ridges form where
+    // the source crosses zero, so it should give values in [-1,1] like Simplex
+    pub fn from_source(source: S, octaves: i32, gain: f64, lac: f64, offset: f64, h: f64) -> RidgedMulti<S> {
+        let freqs = calc_freqs(octaves, lac, h);
         RidgedMulti {  
-            simp: Simplex::from_seed(seed, zoom),
-            octaves: octaves,
-            gain: gain,
+            source,
+            octaves,
+            gain,
             lacunarity: lac,
-            offset: offset,
-            frequencies: freqs.clone(),
-            max_val: RidgedMulti::calc_max(octaves, gain, offset, freqs)
+            offset,
+            h,
+            max_val: Self::calc_max(octaves, gain, offset, &freqs),
+            frequencies: freqs,
+            octave_seed: 0,
+            offsets: octave_offsets(0, octaves.max(0) as usize + 1),
             }
     }
 
-    pub fn get_seed(&mut self) -> u32 {
-        self.simp.get_seed()
+    pub fn source(&self) -> &S {
+        &self.source
     }
 
-    fn calc_freqs(octaves: int, lacunarity: f64, h: f64) -> Vec<f64> {
-        let mut freqs: Vec<f64> = Vec::new();
+    // Nothing here is cached from the source, so it's safe to change in place
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
 
-        for i in range(0, octaves) {
-            let f = lacunarity.powf((-i as f64) * h);
-            freqs.push(f);
-        }
+    pub fn set_octaves(&mut self, octaves: i32) -> Result<(), BuildError> {
+        check_octaves(octaves)?;
+        self.octaves = octaves;
+        self.offsets = octave_offsets(self.octave_seed, octaves as usize + 1);
+        self.update_freqs();
+        Ok(())
+    }
+
+    pub fn get_octaves(&self) -> i32 {
+        self.octaves
+    }
+
+    pub fn set_gain(&mut self, gain: f64) {
+        self.gain = gain;
+        self.update_max();
+    }
+
+    pub fn get_gain(&self) -> f64 {
+        self.gain
+    }
+
+    pub fn set_lacunarity(&mut self, lac: f64) -> Result<(), BuildError> {
+        check_lacunarity(lac)?;
+        self.lacunarity = lac;
+        self.update_freqs();
+        Ok(())
+    }
+
+    pub fn get_lacunarity(&self) -> f64 {
+        self.lacunarity
+    }
+
+    pub fn set_offset(&mut self, offset: f64) {
+        self.offset = offset;
+        self.update_max();
+    }
+
+    pub fn get_offset(&self) -> f64 {
+        self.offset
+    }
 
-        freqs
+    pub fn set_h(&mut self, h: f64) {
+        self.h = h;
+        self.update_freqs();
     }
 
-    fn calc_max(octaves: int, gain: f64, offset: f64, freqs: Vec<f64>) -> f64 {
-        let mut signal = RidgedMulti::ridge(0.0, offset);
+    pub fn get_h(&self) -> f64 {
+        self.h
+    }
+
+    // The frequencies depend on octaves, lacunarity and H, and max_val on
+    // those plus gain and offset
+    fn update_freqs(&mut self) {
+        self.frequencies = calc_freqs(self.octaves, self.lacunarity, self.h);
+        self.update_max();
+    }
+
+    fn update_max(&mut self) {
+        self.max_val = Self::calc_max(self.octaves, self.gain, self.offset, &self.frequencies);
+    }
+
+    // Octaves after the first are moved by offsets drawn from this seed, see
+    // octave_offsets in gen/mod.rs. The Simplex constructors use the simplex
+    // seed, from_source starts at 0.
+    pub fn set_octave_seed(&mut self, seed: u32) {
+        self.octave_seed = seed;
+        self.offsets = octave_offsets(seed, self.offsets.len());
+    }
+
+    pub fn get_octave_seed(&self) -> u32 {
+        self.octave_seed
+    }
+
+    fn calc_max(octaves: i32, gain: f64, offset: f64, freqs: &[f64]) -> f64 {
+        let mut signal = Self::ridge(0.0, offset);
         let mut weight: f64;
         let mut sum = signal;
-        for i in range(0, octaves) {
+        for i in 0..octaves {
             weight = signal * gain;
             weight = clamp(weight, 0.0, 1.0);
-            signal = RidgedMulti::ridge(0.0, offset);
+            signal = Self::ridge(0.0, offset);
             signal *= weight;
-            sum += signal * freqs[i as uint];
+            sum += signal * freqs[i as usize];
         }
         
         sum
     }
 
     fn ridge(n: f64, offset: f64) -> f64 {
-        let n = offset - num::abs(n);
+        let n = offset - n.abs();
         n * n
     }
 }
 
-impl NoiseGen for RidgedMulti {
-    fn get_value2d(&mut self, x: f64, y: f64) -> f64 {
+impl<S: NoiseGen> NoiseGen for RidgedMulti<S> {
+    fn get_value1d(&self, x: f64) -> f64 {
+        let mut xx = x;
+        let mut signal = Self::ridge(
+                            self.source.get_value1d(x),
+                            self.offset);
+        let mut sum = signal;
+        let mut weight: f64;
+
+        for (i, o) in self.offsets[1..].iter().enumerate() {
+            xx *= self.lacunarity;
+            weight = signal * self.gain;
+            weight = clamp(weight, 0.0, 1.0);
+            signal = Self::ridge(
+                        self.source.get_value1d(xx + o[0]),
+                        self.offset);
+            signal *= weight;
+            sum += signal * self.frequencies[i];
+        }
+
+        bound(sum, 0.0, 1.0, 0.0, self.max_val)
+    }
+
+    fn get_value2d(&self, x: f64, y: f64) -> f64 {
+        let mut xx = x;
+        let mut yy = y;
+        let mut signal = Self::ridge(
+                            self.source.get_value2d(x, y),
+                            self.offset);
+        let mut sum = signal;
+        let mut weight: f64;
+
+        for (i, o) in self.offsets[1..].iter().enumerate() {
+            xx *= self.lacunarity;
+            yy *= self.lacunarity;
+            weight = signal * self.gain;
+            weight = clamp(weight, 0.0, 1.0);
+            signal = Self::ridge(
+                        self.source.get_value2d(xx + o[0], yy + o[1]),
+                        self.offset);
+            signal *= weight;
+            sum += signal * self.frequencies[i];
+        }
+
+        bound(sum, 0.0, 1.0, 0.0, self.max_val)
+    }
+
+    fn get_value3d(&self, x: f64, y: f64, z: f64) -> f64 {
         let mut xx = x;
         let mut yy = y;
-        let mut signal = RidgedMulti::ridge(
-                            self.simp.get_value2d(x, y),
+        let mut zz = z;
+        let mut signal = Self::ridge(
+                            self.source.get_value3d(x, y, z),
                             self.offset);
         let mut sum = signal;
         let mut weight: f64;
 
-        for i in range(0, self.octaves) {
-            xx = xx * self.lacunarity;
-            yy = yy * self.lacunarity;
+        for (i, o) in self.offsets[1..].iter().enumerate() {
+            xx *= self.lacunarity;
+            yy *= self.lacunarity;
+            zz *= self.lacunarity;
             weight = signal * self.gain;
             weight = clamp(weight, 0.0, 1.0);
-            signal = RidgedMulti::ridge(
-                        self.simp.get_value2d(xx, yy),
+            signal = Self::ridge(
+                        self.source.get_value3d(xx + o[0], yy + o[1], zz + o[2]),
                         self.offset);
             signal *= weight;
-            sum += signal * self.frequencies[i as uint];
+            sum += signal * self.frequencies[i];
         }
 
         bound(sum, 0.0, 1.0, 0.0, self.max_val)
     }
 
-    fn get_value3d(&mut self, x: f64, y: f64, z: f64) -> f64 {
+    fn get_value4d(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
         let mut xx = x;
         let mut yy = y;
         let mut zz = z;
-        let mut signal = RidgedMulti::ridge(
-                            self.simp.get_value3d(x, y, z),
+        let mut ww = w;
+        let mut signal = Self::ridge(
+                            self.source.get_value4d(x, y, z, w),
                             self.offset);
         let mut sum = signal;
         let mut weight: f64;
 
-        for i in range(0, self.octaves) {
-            xx = xx * self.lacunarity;
-            yy = yy * self.lacunarity;
-            zz = zz * self.lacunarity;
+        for (i, o) in self.offsets[1..].iter().enumerate() {
+            xx *= self.lacunarity;
+            yy *= self.lacunarity;
+            zz *= self.lacunarity;
+            ww *= self.lacunarity;
             weight = signal * self.gain;
             weight = clamp(weight, 0.0, 1.0);
-            signal = RidgedMulti::ridge(
-                        self.simp.get_value3d(xx, yy, zz),
+            signal = Self::ridge(
+                        self.source.get_value4d(xx + o[0], yy + o[1], zz + o[2], ww + o[3]),
                         self.offset);
             signal *= weight;
-            sum += signal * self.frequencies[i as uint];
+            sum += signal * self.frequencies[i];
         }
 
         bound(sum, 0.0, 1.0, 0.0, self.max_val)
     }
+
+    fn fill_2d(&self, origin: (f64, f64), step: f64, width: usize, height: usize, out: &mut [f64]) {
+        let len = width * height;
+        assert!(out.len() >= len, "fill_2d: buffer is smaller than width * height");
+        let out = &mut out[..len];
+
+        // Run a whole octave at a time so the source's fill can reuse its row work,
+        // keeping each point's previous signal around for the weights
+        let mut octave = vec![0.0; len];
+        self.source.fill_2d(origin, step, width, height, &mut octave);
+        let mut signal: Vec<f64> = octave.iter().map(|&n| Self::ridge(n, self.offset)).collect();
+        out.copy_from_slice(&signal);
+
+        let mut f = 1.0;
+        for (i, o) in self.offsets[1..].iter().enumerate() {
+            f *= self.lacunarity;
+            self.source.fill_2d((origin.0*f + o[0], origin.1*f + o[1]), step*f, width, height, &mut octave);
+            for ((n, s), &v) in out.iter_mut().zip(signal.iter_mut()).zip(octave.iter()) {
+                let weight = clamp(*s * self.gain, 0.0, 1.0);
+                *s = Self::ridge(v, self.offset) * weight;
+                *n += *s * self.frequencies[i];
+            }
+        }
+
+        for n in out.iter_mut() {
+            *n = bound(*n, 0.0, 1.0, 0.0, self.max_val);
+        }
+    }
+
+    fn fill_3d(&self, origin: (f64, f64, f64), step: f64, width: usize, height: usize, depth: usize, out: &mut [f64]) {
+        let len = width * height * depth;
+        assert!(out.len() >= len, "fill_3d: buffer is smaller than width * height * depth");
+        let out = &mut out[..len];
+
+        let mut octave = vec![0.0; len];
+        self.source.fill_3d(origin, step, width, height, depth, &mut octave);
+        let mut signal: Vec<f64> = octave.iter().map(|&n| Self::ridge(n, self.offset)).collect();
+        out.copy_from_slice(&signal);
+
+        let mut f = 1.0;
+        for (i, o) in self.offsets[1..].iter().enumerate() {
+            f *= self.lacunarity;
+            self.source.fill_3d((origin.0*f + o[0], origin.1*f + o[1], origin.2*f + o[2]), step*f, width, height, depth, &mut octave);
+            for ((n, s), &v) in out.iter_mut().zip(signal.iter_mut()).zip(octave.iter()) {
+                let weight = clamp(*s * self.gain, 0.0, 1.0);
+                *s = Self::ridge(v, self.offset) * weight;
+                *n += *s * self.frequencies[i];
+            }
+        }
+
+        for n in out.iter_mut() {
+            *n = bound(*n, 0.0, 1.0, 0.0, self.max_val);
+        }
+    }
+}
+
+// Named settings for RidgedMulti, checked when built. Defaults to 6 octaves,
+// gain 2.0, lacunarity 2.0, offset 1.0, H 1.0, zoom 1.0 and a random seed.
+//
+//     let ngen = RidgedMulti::builder().seed(7).gain(1.7).h(0.75).build()?;
+#[derive(Clone, Debug)]
+pub struct RidgedMultiBuilder {
+    seed: Option<u32>,
+    octaves: i32,
+    gain: f64,
+    lacunarity: f64,
+    offset: f64,
+    h: f64,
+    zoom: f64,
+}
+
+impl RidgedMulti {
+    pub fn builder() -> RidgedMultiBuilder {
+        RidgedMultiBuilder::default()
+    }
+}
+
+impl Default for RidgedMultiBuilder {
+    fn default() -> RidgedMultiBuilder {
+        RidgedMultiBuilder {
+            seed: None,
+            octaves: 6,
+            gain: 2.0,
+            lacunarity: 2.0,
+            offset: 1.0,
+            h: 1.0,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl RidgedMultiBuilder {
+    pub fn seed(mut self, seed: u32) -> RidgedMultiBuilder {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn octaves(mut self, octaves: i32) -> RidgedMultiBuilder {
+        self.octaves = octaves;
+        self
+    }
+
+    pub fn gain(mut self, gain: f64) -> RidgedMultiBuilder {
+        self.gain = gain;
+        self
+    }
+
+    pub fn lacunarity(mut self, lacunarity: f64) -> RidgedMultiBuilder {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    pub fn offset(mut self, offset: f64) -> RidgedMultiBuilder {
+        self.offset = offset;
+        self
+    }
+
+    pub fn h(mut self, h: f64) -> RidgedMultiBuilder {
+        self.h = h;
+        self
+    }
+
+    pub fn zoom(mut self, zoom: f64) -> RidgedMultiBuilder {
+        self.zoom = zoom;
+        self
+    }
+
+    pub fn build(self) -> Result<RidgedMulti, BuildError> {
+        check_zoom(self.zoom)?;
+        let simp = match self.seed {
+            Some(seed) => Simplex::from_seed(seed, self.zoom),
+            None => Simplex::new_rand(self.zoom),
+        };
+        let seed = simp.get_seed();
+        self.seed(seed).build_with_source(simp)
+    }
+
+    // Builds ridged octaves of source instead of Simplex. zoom is left to the
+    // source, and the seed, if any, only seeds the octave offsets.
+    pub fn build_with_source<S: NoiseGen>(self, source: S) -> Result<RidgedMulti<S>, BuildError> {
+        check_octaves(self.octaves)?;
+        check_lacunarity(self.lacunarity)?;
+        let mut ngen = RidgedMulti::from_source(source, self.octaves, self.gain, self.lacunarity, self.offset, self.h);
+        ngen.set_octave_seed(self.seed.unwrap_or(0));
+        Ok(ngen)
+    }
 }
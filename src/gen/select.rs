@@ -0,0 +1,93 @@
+// select.rs
+
+// Picks between two generators with a third. Where the control is above the
+// threshold the output is high, below it low, and within falloff of it the
+// two are blended. Select is a generator itself, so they can be nested or
+// used as the source of a fractal.
+//
+//     let coast = Select::new(continents, sea, land, 0.5, 0.05);
+//     let ngen = FBM::from_source(coast, 4, 0.5, 2.0);
+
+use crate::gen::NoiseGen;
+use crate::utils::select_with;
+
+pub struct Select<C, L, H> {
+    control: C,
+    low: L,
+    high: H,
+    threshold: f64,
+    falloff: f64,
+}
+
+impl<C: NoiseGen, L: NoiseGen, H: NoiseGen> Select<C, L, H> {
+    pub fn new(control: C, low: L, high: H, threshold: f64, falloff: f64) -> Select<C, L, H> {
+        Select { control, low, high, threshold, falloff }
+    }
+
+    pub fn control(&self) -> &C {
+        &self.control
+    }
+
+    pub fn control_mut(&mut self) -> &mut C {
+        &mut self.control
+    }
+
+    pub fn low(&self) -> &L {
+        &self.low
+    }
+
+    pub fn low_mut(&mut self) -> &mut L {
+        &mut self.low
+    }
+
+    pub fn high(&self) -> &H {
+        &self.high
+    }
+
+    pub fn high_mut(&mut self) -> &mut H {
+        &mut self.high
+    }
+
+    pub fn set_threshold(&mut self, threshold: f64) {
+        self.threshold = threshold;
+    }
+
+    pub fn get_threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    // Half the width of the blend around the threshold, 0 for a hard edge
+    pub fn set_falloff(&mut self, falloff: f64) {
+        self.falloff = falloff;
+    }
+
+    pub fn get_falloff(&self) -> f64 {
+        self.falloff
+    }
+}
+
+impl<C: NoiseGen, L: NoiseGen, H: NoiseGen> NoiseGen for Select<C, L, H> {
+    fn get_value1d(&self, x: f64) -> f64 {
+        select_with(self.control.get_value1d(x), self.threshold, self.falloff,
+                    || self.low.get_value1d(x),
+                    || self.high.get_value1d(x))
+    }
+
+    fn get_value2d(&self, x: f64, y: f64) -> f64 {
+        select_with(self.control.get_value2d(x, y), self.threshold, self.falloff,
+                    || self.low.get_value2d(x, y),
+                    || self.high.get_value2d(x, y))
+    }
+
+    fn get_value3d(&self, x: f64, y: f64, z: f64) -> f64 {
+        select_with(self.control.get_value3d(x, y, z), self.threshold, self.falloff,
+                    || self.low.get_value3d(x, y, z),
+                    || self.high.get_value3d(x, y, z))
+    }
+
+    fn get_value4d(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+        select_with(self.control.get_value4d(x, y, z, w), self.threshold, self.falloff,
+                    || self.low.get_value4d(x, y, z, w),
+                    || self.high.get_value4d(x, y, z, w))
+    }
+}
@@ -0,0 +1,140 @@
+// value.rs
+
+// Value noise: a random value at every lattice point, blended across each
+// cell. Cheaper than the gradient noises, and blockier, especially with the
+// linear curve.
+
+use crate::lcgrng::random_seed;
+use crate::gen::{NoiseGen, seeded_perm, perm_hash};
+use crate::utils::{lerp, trilerp, blend_cubic, blend_quintic};
+
+// How values are blended across a cell
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Curve {
+    // Straight lines between lattice points, with creases along the cell edges
+    Linear,
+    // 3t^2 - 2t^3, smooth slopes but a kink in the curvature at the edges
+    Cubic,
+    // 6t^5 - 15t^4 + 10t^3, smooth through the second derivative
+    Quintic,
+}
+
+impl Curve {
+    fn apply(self, t: f64) -> f64 {
+        match self {
+            Curve::Linear => t,
+            Curve::Cubic => blend_cubic(t),
+            Curve::Quintic => blend_quintic(t),
+        }
+    }
+}
+
+// Values are in [-1,1]
+pub struct Value {
+    seed: u32,
+    zoom: f64,
+    curve: Curve,
+    perm: [u8; 256]
+}
+
+impl Value {
+    // Defaults to the quintic curve
+    pub fn new_rand(zoom: f64) -> Value {
+        Value::from_seed(random_seed(), zoom)
+    }
+
+    pub fn from_seed(seed: u32, zoom: f64) -> Value {
+        Value { seed, zoom: 1.0/zoom, curve: Curve::Quintic, perm: seeded_perm(seed) }
+    }
+
+    pub fn set_seed(&mut self, seed: u32) {
+        self.seed = seed;
+        self.perm = seeded_perm(seed);
+    }
+
+    pub fn get_seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn set_zoom(&mut self, zoom: f64) {
+        self.zoom = 1.0/zoom;
+    }
+
+    pub fn get_zoom(&self) -> f64 {
+        1.0/self.zoom
+    }
+
+    pub fn set_curve(&mut self, curve: Curve) {
+        self.curve = curve;
+    }
+
+    pub fn get_curve(&self) -> Curve {
+        self.curve
+    }
+
+    // One pass through perm leaves each column of the lattice a shifted copy
+    // of the one before it. Gradients hide that but plain values show it as
+    // streaks, so this goes back over the coordinates in reverse as well.
+    fn lattice(&self, lattice: &[i64]) -> f64 {
+        let mut h = perm_hash(&self.perm, lattice);
+        for &l in lattice.iter().rev() {
+            h = self.perm[((h as usize) + ((l & 0xFF) as usize)) & 0xFF];
+        }
+        (h as f64) * (2.0 / 255.0) - 1.0
+    }
+
+    // Blends the eight corners of the cube at [xi, yi, zi], with the w lattice
+    // coordinate appended when there is one
+    fn cube(&self, [xi, yi, zi]: [i64; 3], w: Option<i64>, [tx, ty, tz]: [f64; 3]) -> f64 {
+        let v = |x: i64, y: i64, z: i64| match w {
+            Some(w) => self.lattice(&[x, y, z, w]),
+            None => self.lattice(&[x, y, z]),
+        };
+
+        trilerp(v(xi, yi, zi), v(xi + 1, yi, zi), v(xi, yi + 1, zi), v(xi + 1, yi + 1, zi),
+                v(xi, yi, zi + 1), v(xi + 1, yi, zi + 1), v(xi, yi + 1, zi + 1), v(xi + 1, yi + 1, zi + 1),
+                tx, ty, tz)
+    }
+}
+
+impl NoiseGen for Value {
+    fn get_value1d(&self, x: f64) -> f64 {
+        let x = x * self.zoom;
+        let xi = x.floor() as i64;
+
+        lerp(self.lattice(&[xi]), self.lattice(&[xi + 1]), self.curve.apply(x - x.floor()))
+    }
+
+    fn get_value2d(&self, x: f64, y: f64) -> f64 {
+        let x = x * self.zoom;
+        let y = y * self.zoom;
+        let (xi, yi) = (x.floor() as i64, y.floor() as i64);
+        let tx = self.curve.apply(x - x.floor());
+        let ty = self.curve.apply(y - y.floor());
+
+        let u = lerp(self.lattice(&[xi, yi]), self.lattice(&[xi + 1, yi]), tx);
+        let v = lerp(self.lattice(&[xi, yi + 1]), self.lattice(&[xi + 1, yi + 1]), tx);
+        lerp(u, v, ty)
+    }
+
+    fn get_value3d(&self, x: f64, y: f64, z: f64) -> f64 {
+        let x = x * self.zoom;
+        let y = y * self.zoom;
+        let z = z * self.zoom;
+        let t = [x - x.floor(), y - y.floor(), z - z.floor()].map(|t| self.curve.apply(t));
+
+        self.cube([x.floor() as i64, y.floor() as i64, z.floor() as i64], None, t)
+    }
+
+    fn get_value4d(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+        let x = x * self.zoom;
+        let y = y * self.zoom;
+        let z = z * self.zoom;
+        let w = w * self.zoom;
+        let lattice = [x.floor() as i64, y.floor() as i64, z.floor() as i64];
+        let t = [x - x.floor(), y - y.floor(), z - z.floor()].map(|t| self.curve.apply(t));
+        let wi = w.floor() as i64;
+
+        lerp(self.cube(lattice, Some(wi), t), self.cube(lattice, Some(wi + 1), t), self.curve.apply(w - w.floor()))
+    }
+}
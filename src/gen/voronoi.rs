@@ -1,97 +1,403 @@
+// voronoi.rs
 
-use gen::NoiseGen;
-use gen::simplex::Simplex;
+// Worley (cellular) noise. Every lattice cell holds one feature point, moved
+// from the cell centre by a hash of the cell on each axis, and the output is
+// made from the distances to the nearest feature points.
+
+use crate::gen::NoiseGen;
+use crate::lcgrng::random_seed;
+use crate::error::{BuildError, check_zoom, check_jitter, check_minkowski, check_border_width};
+
+// Cells searched on each side of the one holding the point. With a jitter of
+// at most 1 the feature points stay inside their cells, which puts anything
+// further out beyond the second nearest. Nearest cells go first so the rest
+// can be skipped once they can't get any closer.
+const SEARCH: [i64; 5] = [0, -1, 1, -2, 2];
+
+// What the noise is made from. F1 and F2 are the distances to the nearest
+// and second nearest feature points, in cells. The distances aren't bounded
+// to [-1,1], CellValue and Border are.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Output {
+    F1,
+    F2,
+    F2MinusF1,
+    F1TimesF2,
+    // A random value in [-1,1] for the cell of the nearest feature point
+    CellValue,
+    // Exact distance to the nearest cell border, in cells. Unlike F2-F1 it
+    // doesn't pinch in where the feature points are far apart. Borders are
+    // always found with Euclidean distance, since under the other metrics
+    // they aren't straight lines.
+    Edge,
+    // 1 on the cell borders, falling to -1 at the given distance from them
+    // and staying there, for cracks and walls of that width
+    Border(f64),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Metric {
+    Euclidean,
+    // Euclidean without the square root, which gives rounder cells
+    EuclideanSquared,
+    Manhattan,
+    Chebyshev,
+    // (sum |d|^p)^(1/p). p = 1 is Manhattan, p = 2 Euclidean and larger p
+    // tend towards Chebyshev. Below 1 cells further along an axis than the
+    // search reaches can be nearer than diagonal ones inside it, so p must be
+    // at least 1.
+    Minkowski(f64),
+}
+
+impl Metric {
+    fn distance<const N: usize>(self, d: &[f64; N]) -> f64 {
+        match self {
+            Metric::Euclidean => d.iter().map(|a| a*a).sum::<f64>().sqrt(),
+            Metric::EuclideanSquared => d.iter().map(|a| a*a).sum(),
+            Metric::Manhattan => d.iter().map(|a| a.abs()).sum(),
+            Metric::Chebyshev => d.iter().fold(0.0, |m, a| a.abs().max(m)),
+            Metric::Minkowski(p) => d.iter().map(|a| a.abs().powf(p)).sum::<f64>().powf(1.0/p),
+        }
+    }
+}
+
+// The cell a point falls in, from Voronoi::get_cell2d and get_cell3d
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cell<const N: usize> {
+    // lattice coordinates of the cell whose feature point is nearest
+    pub cell: [i64; N],
+    // hash of the seed and cell, the same for every point in the cell and
+    // between runs. Truncate it if 32 bits is enough.
+    pub id: u64,
+    // the feature point, in the same coordinates as the query
+    pub point: [f64; N],
+    // distance to the feature point under the current metric, in cells like F1
+    pub distance: f64,
+}
 
 pub struct Voronoi {
-    simp: Simplex,
-    zoom: f64
+    seed: u32,
+    zoom: f64,
+    jitter: f64,
+    output: Output,
+    metric: Metric,
 }
 
 impl Voronoi {
+    // Defaults to fully jittered cells, cell values and Euclidean distance
     pub fn new_rand(zoom: f64) -> Voronoi {
-        Voronoi { simp: Simplex::new_rand(1.0), zoom: 1.0/zoom }
+        Voronoi::from_seed(random_seed(), zoom)
     }
 
-    pub fn from_seed(seed:u32, zoom: f64) -> Voronoi {
-        Voronoi { simp: Simplex::from_seed(seed, 1.0), zoom: 1.0/zoom }
+    pub fn from_seed(seed: u32, zoom: f64) -> Voronoi {
+        Voronoi {
+            seed,
+            zoom: 1.0/zoom,
+            jitter: 1.0,
+            output: Output::CellValue,
+            metric: Metric::Euclidean,
+        }
     }
 
-    pub fn get_seed(&mut self) -> u32 {
-        self.simp.get_seed()
+    pub fn set_seed(&mut self, seed: u32) {
+        self.seed = seed;
     }
-}
 
-impl NoiseGen for Voronoi {
-    fn get_value2d(&mut self, x: f64, y: f64) -> f64 {
-        let xx = x * self.zoom;
-        let yy = y * self.zoom;
-        let xi = xx.floor() as int;
-        let yi = yy.floor() as int;
-
-        let mut min_dist = 2147483647.0; // 2^31
-        // candidates for our x and y values
-        let mut x_can = 0.0;
-        let mut y_can = 0.0;
-
-        for cur_y in range(yi-2, yi+2+1) {
-            for cur_x in range(xi-2, xi+2+1) {
-                let n = self.simp.get_value2d(cur_x as f64, cur_y as f64);
-                let x_pos = cur_x as f64 + n;
-                let y_pos = cur_y as f64 + n;
-                let x_dist = x_pos - xx;
-                let y_dist = y_pos - yy;
-                let dist = (x_dist*x_dist) + (y_dist*y_dist);
-
-                if dist < min_dist {
-                    min_dist = dist;
-                    x_can = x_pos;
-                    y_can = y_pos;
-                }
+    pub fn get_seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn set_zoom(&mut self, zoom: f64) {
+        self.zoom = 1.0/zoom;
+    }
+
+    pub fn get_zoom(&self) -> f64 {
+        1.0/self.zoom
+    }
+
+    // How far feature points can move from their cell centres, from 0 for a
+    // regular grid up to 1 where they can be anywhere in the cell. Values
+    // outside [0,1] let points leave their cells and the search miss them.
+    pub fn set_jitter(&mut self, jitter: f64) {
+        self.jitter = jitter;
+    }
+
+    pub fn get_jitter(&self) -> f64 {
+        self.jitter
+    }
+
+    pub fn set_output(&mut self, output: Output) {
+        self.output = output;
+    }
+
+    pub fn get_output(&self) -> Output {
+        self.output
+    }
+
+    // Like set_jitter this isn't checked. Minkowski exponents below 1 give
+    // wrong distances, the builder rejects them.
+    pub fn set_metric(&mut self, metric: Metric) {
+        self.metric = metric;
+    }
+
+    pub fn get_metric(&self) -> Metric {
+        self.metric
+    }
+
+    pub fn get_cell2d(&self, x: f64, y: f64) -> Cell<2> {
+        self.cell([x, y])
+    }
+
+    pub fn get_cell3d(&self, x: f64, y: f64, z: f64) -> Cell<3> {
+        self.cell([x, y, z])
+    }
+
+    fn cell<const N: usize>(&self, mut p: [f64; N]) -> Cell<N> {
+        for a in p.iter_mut() {
+            *a *= self.zoom;
+        }
+
+        let (f1, _, cell) = self.search(p, self.metric);
+        let mut point = self.feature_point(&cell);
+        for a in point.iter_mut() {
+            *a /= self.zoom;
+        }
+
+        let id = ((hash(self.seed, &cell, N as u32 + 1) as u64) << 32) | (hash(self.seed, &cell, N as u32 + 2) as u64);
+        Cell { cell, id, point, distance: f1 }
+    }
+
+    fn feature_point<const N: usize>(&self, cell: &[i64; N]) -> [f64; N] {
+        let mut p = [0.0; N];
+        for (axis, a) in p.iter_mut().enumerate() {
+            let h = to_unit(hash(self.seed, cell, axis as u32));
+            *a = cell[axis] as f64 + 0.5 + self.jitter * (h - 0.5);
+        }
+        p
+    }
+
+    // Finds F1, F2 and the cell of the nearest feature point
+    fn search<const N: usize>(&self, p: [f64; N], metric: Metric) -> (f64, f64, [i64; N]) {
+        let mut base = [0; N];
+        for (b, a) in base.iter_mut().zip(p.iter()) {
+            *b = a.floor() as i64;
+        }
+
+        let mut f1 = f64::INFINITY;
+        let mut f2 = f64::INFINITY;
+        let mut nearest = base;
+
+        for i in 0..SEARCH.len().pow(N as u32) {
+            let (cell, gap) = neighbour(&base, i, &p);
+
+            // Every metric grows with each axis, so nothing in this cell can be nearer
+            if metric.distance(&gap) >= f2 {
+                continue;
+            }
+
+            let fp = self.feature_point(&cell);
+            let mut d = [0.0; N];
+            for axis in 0..N {
+                d[axis] = fp[axis] - p[axis];
+            }
+            let dist = metric.distance(&d);
+
+            if dist < f1 {
+                f2 = f1;
+                f1 = dist;
+                nearest = cell;
+            } else if dist < f2 {
+                f2 = dist;
             }
         }
 
-        self.simp.get_value2d(x_can.floor(), y_can.floor())
-    }
-
-    fn get_value3d(&mut self, x: f64, y: f64, z: f64) -> f64 {
-        let xx = x * self.zoom;
-        let yy = y * self.zoom;
-        let zz = z * self.zoom;
-        let xi = xx.floor() as int;
-        let yi = yy.floor() as int;
-        let zi = zz.floor() as int;
-
-        let mut min_dist = 2147483647.0; // 2^31
-        // candidates for our x and y values
-        let mut x_can = 0.0;
-        let mut y_can = 0.0;
-        let mut z_can = 0.0;
-
-        for cur_z in range(zi-2, zi+2+1){
-            for cur_y in range(yi-2, yi+2+1) {
-                for cur_x in range(xi-2, xi+2+1) {
-                    let n = self.simp.get_value3d(  cur_x as f64,
-                                                    cur_y as f64,
-                                                    cur_z as f64);
-                    let x_pos = cur_x as f64 + n;
-                    let y_pos = cur_y as f64 + n;
-                    let z_pos = cur_z as f64 + n;
-                    let x_dist = x_pos - xx;
-                    let y_dist = y_pos - yy;
-                    let z_dist = z_pos - zz;
-                    let dist = (x_dist*x_dist) + (y_dist*y_dist) + (z_dist*z_dist);
-
-                    if dist < min_dist {
-                        min_dist = dist;
-                        x_can = x_pos;
-                        y_can = y_pos;
-                        z_can = z_pos;
-                    }
-                }
+        (f1, f2, nearest)
+    }
+
+    // The border nearest to p lies on the plane halfway between the nearest
+    // feature point and one of the others, so this takes the closest of those
+    // planes. From https://iquilezles.org/articles/voronoilines/
+    fn edge<const N: usize>(&self, p: [f64; N]) -> f64 {
+        let (f1, _, nearest) = self.search(p, Metric::Euclidean);
+        let p1 = self.feature_point(&nearest);
+
+        let mut edge = f64::INFINITY;
+        for i in 1..SEARCH.len().pow(N as u32) {
+            let (cell, gap) = neighbour(&nearest, i, &p);
+
+            // A plane is at least half as far as its point is beyond p1
+            if (Metric::Euclidean.distance(&gap) - f1) * 0.5 >= edge {
+                continue;
             }
+
+            let fp = self.feature_point(&cell);
+            let mut along = 0.0;
+            let mut len = 0.0;
+            for axis in 0..N {
+                let n = fp[axis] - p1[axis];
+                let mid = (fp[axis] + p1[axis]) * 0.5;
+                along += (mid - p[axis]) * n;
+                len += n * n;
+            }
+            edge = edge.min(along / len.sqrt());
+        }
+
+        edge
+    }
+
+    fn value<const N: usize>(&self, mut p: [f64; N]) -> f64 {
+        for a in p.iter_mut() {
+            *a *= self.zoom;
+        }
+
+        let search = || self.search(p, self.metric);
+        match self.output {
+            Output::F1 => search().0,
+            Output::F2 => search().1,
+            Output::F2MinusF1 => {
+                let (f1, f2, _) = search();
+                f2 - f1
+            },
+            Output::F1TimesF2 => {
+                let (f1, f2, _) = search();
+                f1 * f2
+            },
+            Output::CellValue => to_unit(hash(self.seed, &search().2, N as u32)) * 2.0 - 1.0,
+            Output::Edge => self.edge(p),
+            Output::Border(width) => 1.0 - 2.0 * (self.edge(p) / width).min(1.0),
         }
+    }
+}
+
+impl NoiseGen for Voronoi {
+    fn get_value1d(&self, x: f64) -> f64 {
+        self.value([x])
+    }
+
+    fn get_value2d(&self, x: f64, y: f64) -> f64 {
+        self.value([x, y])
+    }
+
+    fn get_value3d(&self, x: f64, y: f64, z: f64) -> f64 {
+        self.value([x, y, z])
+    }
+
+    fn get_value4d(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+        self.value([x, y, z, w])
+    }
+}
+
+// The i-th cell searched around base, and how far p is from it along each axis
+fn neighbour<const N: usize>(base: &[i64; N], i: usize, p: &[f64; N]) -> ([i64; N], [f64; N]) {
+    let mut cell = *base;
+    let mut gap = [0.0; N];
+    let mut k = i;
+    for axis in 0..N {
+        cell[axis] += SEARCH[k % SEARCH.len()];
+        k /= SEARCH.len();
+        let lo = cell[axis] as f64;
+        gap[axis] = (lo - p[axis]).max(p[axis] - (lo + 1.0)).max(0.0);
+    }
+    (cell, gap)
+}
+
+// Hashes a cell for one of its random values. The feature point takes salts
+// 0..N, one per axis, the cell value takes N and the two halves of the cell
+// ID take N+1 and N+2. Only the low 32 bits of each coordinate are used, so
+// the pattern repeats every 2^32 cells.
+fn hash<const N: usize>(seed: u32, cell: &[i64; N], salt: u32) -> u32 {
+    let mut h = mix(seed ^ salt.wrapping_mul(0x9e3779b9));
+    for &c in cell.iter() {
+        h = mix(h ^ (c as u32));
+    }
+    h
+}
+
+// lowbias32 from https://nullprogram.com/blog/2018/07/31/
+fn mix(mut x: u32) -> u32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846ca68b);
+    x ^= x >> 16;
+    x
+}
+
+// Maps a hash onto [0,1)
+fn to_unit(h: u32) -> f64 {
+    (h as f64) * (1.0 / 4294967296.0)
+}
+
+// Named settings for Voronoi, checked when built. Defaults to zoom 1.0,
+// jitter 1.0, cell values, Euclidean distance and a random seed.
+//
+//     let ngen = Voronoi::builder().zoom(30.0).output(Output::F2MinusF1).build()?;
+#[derive(Clone, Debug)]
+pub struct VoronoiBuilder {
+    seed: Option<u32>,
+    zoom: f64,
+    jitter: f64,
+    output: Output,
+    metric: Metric,
+}
 
-        self.simp.get_value3d(x_can.floor(), y_can.floor(), z_can.floor())
+impl Voronoi {
+    pub fn builder() -> VoronoiBuilder {
+        VoronoiBuilder::default()
     }
 }
 
+impl Default for VoronoiBuilder {
+    fn default() -> VoronoiBuilder {
+        VoronoiBuilder {
+            seed: None,
+            zoom: 1.0,
+            jitter: 1.0,
+            output: Output::CellValue,
+            metric: Metric::Euclidean,
+        }
+    }
+}
+
+impl VoronoiBuilder {
+    pub fn seed(mut self, seed: u32) -> VoronoiBuilder {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn zoom(mut self, zoom: f64) -> VoronoiBuilder {
+        self.zoom = zoom;
+        self
+    }
+
+    pub fn jitter(mut self, jitter: f64) -> VoronoiBuilder {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn output(mut self, output: Output) -> VoronoiBuilder {
+        self.output = output;
+        self
+    }
+
+    pub fn metric(mut self, metric: Metric) -> VoronoiBuilder {
+        self.metric = metric;
+        self
+    }
+
+    pub fn build(self) -> Result<Voronoi, BuildError> {
+        check_zoom(self.zoom)?;
+        check_jitter(self.jitter)?;
+        if let Metric::Minkowski(p) = self.metric {
+            check_minkowski(p)?;
+        }
+        if let Output::Border(width) = self.output {
+            check_border_width(width)?;
+        }
+        let mut ngen = Voronoi::from_seed(self.seed.unwrap_or_else(random_seed), self.zoom);
+        ngen.set_jitter(self.jitter);
+        ngen.set_output(self.output);
+        ngen.set_metric(self.metric);
+        Ok(ngen)
+    }
+}
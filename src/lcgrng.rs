@@ -1,30 +1,55 @@
 // lcdrng.rs
-// implimentation of 64 bit linear congruential generator
+// implimentation of 32 bit linear congruential generator
 
-use std::rand::{Rng, SeedableRng};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub struct LCG {
     seed: u32,
     prev_val: u32,
 }
 
-impl Rng for LCG {
-    fn next_u32(&mut self) -> u32 {
-        static A: u32 = 1664525;
-        static C: u32 = 1013904223;
-        self.prev_val = (A*self.prev_val)+C; // Normally a bit mask, but is it necessary?
-        self.prev_val
+impl LCG {
+    pub fn from_seed(seed: u32) -> LCG {
+        LCG { seed, prev_val: seed }
     }
-}
 
-impl SeedableRng<u32> for LCG {
-    fn reseed(&mut self, seed: u32) {
+    pub fn reseed(&mut self, seed: u32) {
         self.seed = seed;
         self.prev_val = seed;
     }
 
-    fn from_seed(seed: u32) -> LCG {
-        let lcg = LCG {seed: seed, prev_val: seed};
-        lcg
+    pub fn get_seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        const A: u32 = 1664525;
+        const C: u32 = 1013904223;
+        // Wrapping arithmetic is the implicit mod 2^32
+        self.prev_val = A.wrapping_mul(self.prev_val).wrapping_add(C);
+        self.prev_val
     }
+
+    // Fisher-Yates shuffle
+    pub fn shuffle<T>(&mut self, values: &mut [T]) {
+        for i in (1..values.len()).rev() {
+            // The low bits of an LCG have short periods, so pick j from the high bits
+            let j = ((self.next_u32() as u64 * (i as u64 + 1)) >> 32) as usize;
+            values.swap(i, j);
+        }
+    }
+}
+
+// Seed for the new_rand constructors. RandomState is keyed randomly per
+// process, which avoids pulling in an rng crate just for this.
+pub fn random_seed() -> u32 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(nanos);
+    hasher.finish() as u32
 }
@@ -1,7 +1,5 @@
-
-#![crate_name = "noise"]
-#![crate_type = "rlib"]
-
+pub mod error;
 pub mod gen;
 pub mod utils;
 pub mod lcgrng;
+pub mod region;
@@ -0,0 +1,191 @@
+// src/region.rs
+
+// Builders for sampling a whole region of a generator at once. A region is
+// cut into bands of rows and each band is handed to the generator's fill, so
+// generators with a batched fill keep their speedup. With the rayon feature
+// the bands can be filled on every core; the bands don't depend on the thread
+// count, so build and build_par give exactly the same output.
+
+use crate::gen::NoiseGen;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+const DEFAULT_CHUNK_ROWS: usize = 16;
+
+// A width x height grid of samples, starting at origin and spaced step apart.
+// Output is row by row, the same layout as NoiseGen::fill_2d.
+#[derive(Clone, Copy, Debug)]
+pub struct Region2D {
+    origin: (f64, f64),
+    step: f64,
+    width: usize,
+    height: usize,
+    chunk_rows: usize,
+}
+
+impl Region2D {
+    pub fn new(origin: (f64, f64), step: f64, width: usize, height: usize) -> Region2D {
+        Region2D {
+            origin,
+            step,
+            width,
+            height,
+            chunk_rows: DEFAULT_CHUNK_ROWS,
+        }
+    }
+
+    // Sets how many rows make up one band of work. Changing this can change
+    // the last bits of the output for generators whose fill isn't exact.
+    pub fn chunk_rows(mut self, rows: usize) -> Region2D {
+        assert!(rows > 0, "chunk_rows: must be at least 1");
+        self.chunk_rows = rows;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.width * self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn build<T: NoiseGen>(&self, ngen: &T) -> Vec<f64> {
+        let mut out = vec![0.0; self.len()];
+        self.build_into(ngen, &mut out);
+        out
+    }
+
+    pub fn build_into<T: NoiseGen>(&self, ngen: &T, out: &mut [f64]) {
+        assert!(out.len() >= self.len(), "build_into: buffer is smaller than the region");
+        if self.is_empty() {
+            return;
+        }
+
+        for (c, band) in out[..self.len()].chunks_mut(self.band_len()).enumerate() {
+            self.fill_band(ngen, c, band);
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    pub fn build_par<T: NoiseGen + Sync>(&self, ngen: &T) -> Vec<f64> {
+        let mut out = vec![0.0; self.len()];
+        self.build_par_into(ngen, &mut out);
+        out
+    }
+
+    #[cfg(feature = "rayon")]
+    pub fn build_par_into<T: NoiseGen + Sync>(&self, ngen: &T, out: &mut [f64]) {
+        assert!(out.len() >= self.len(), "build_par_into: buffer is smaller than the region");
+        if self.is_empty() {
+            return;
+        }
+
+        let band_len = self.band_len();
+        out[..self.len()].par_chunks_mut(band_len).enumerate().for_each(|(c, band)| {
+            self.fill_band(ngen, c, band);
+        });
+    }
+
+    fn band_len(&self) -> usize {
+        self.chunk_rows * self.width
+    }
+
+    fn fill_band<T: NoiseGen>(&self, ngen: &T, c: usize, band: &mut [f64]) {
+        let y = self.origin.1 + ((c * self.chunk_rows) as f64) * self.step;
+        let rows = band.len() / self.width;
+        ngen.fill_2d((self.origin.0, y), self.step, self.width, rows, band);
+    }
+}
+
+// A width x height x depth block of samples, slice by slice, the same layout
+// as NoiseGen::fill_3d. Bands never cross from one slice into the next.
+#[derive(Clone, Copy, Debug)]
+pub struct Region3D {
+    origin: (f64, f64, f64),
+    step: f64,
+    width: usize,
+    height: usize,
+    depth: usize,
+    chunk_rows: usize,
+}
+
+impl Region3D {
+    pub fn new(origin: (f64, f64, f64), step: f64, width: usize, height: usize, depth: usize) -> Region3D {
+        Region3D {
+            origin,
+            step,
+            width,
+            height,
+            depth,
+            chunk_rows: DEFAULT_CHUNK_ROWS,
+        }
+    }
+
+    pub fn chunk_rows(mut self, rows: usize) -> Region3D {
+        assert!(rows > 0, "chunk_rows: must be at least 1");
+        self.chunk_rows = rows;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.width * self.height * self.depth
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn build<T: NoiseGen>(&self, ngen: &T) -> Vec<f64> {
+        let mut out = vec![0.0; self.len()];
+        self.build_into(ngen, &mut out);
+        out
+    }
+
+    pub fn build_into<T: NoiseGen>(&self, ngen: &T, out: &mut [f64]) {
+        assert!(out.len() >= self.len(), "build_into: buffer is smaller than the region");
+        if self.is_empty() {
+            return;
+        }
+
+        for (k, slice) in out[..self.len()].chunks_mut(self.width * self.height).enumerate() {
+            for (c, band) in slice.chunks_mut(self.band_len()).enumerate() {
+                self.fill_band(ngen, k, c, band);
+            }
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    pub fn build_par<T: NoiseGen + Sync>(&self, ngen: &T) -> Vec<f64> {
+        let mut out = vec![0.0; self.len()];
+        self.build_par_into(ngen, &mut out);
+        out
+    }
+
+    #[cfg(feature = "rayon")]
+    pub fn build_par_into<T: NoiseGen + Sync>(&self, ngen: &T, out: &mut [f64]) {
+        assert!(out.len() >= self.len(), "build_par_into: buffer is smaller than the region");
+        if self.is_empty() {
+            return;
+        }
+
+        let band_len = self.band_len();
+        out[..self.len()].par_chunks_mut(self.width * self.height).enumerate().for_each(|(k, slice)| {
+            slice.par_chunks_mut(band_len).enumerate().for_each(|(c, band)| {
+                self.fill_band(ngen, k, c, band);
+            });
+        });
+    }
+
+    fn band_len(&self) -> usize {
+        self.chunk_rows * self.width
+    }
+
+    fn fill_band<T: NoiseGen>(&self, ngen: &T, k: usize, c: usize, band: &mut [f64]) {
+        let y = self.origin.1 + ((c * self.chunk_rows) as f64) * self.step;
+        let z = self.origin.2 + (k as f64) * self.step;
+        let rows = band.len() / self.width;
+        ngen.fill_3d((self.origin.0, y, z), self.step, self.width, rows, 1, band);
+    }
+}
@@ -2,16 +2,16 @@
 // utils.rs
 
 #![allow(dead_code)]
+#![allow(clippy::too_many_arguments)]
 
-use std::num;
-use gen::NoiseGen;
+use crate::gen::NoiseGen;
 
 pub fn lerp(low: f64, high: f64, t: f64) -> f64 {
     ((high-low)*t)+low
 }
 
 pub fn ease_curve(t: f64) -> f64 {
-    6.0 * num::pow(t, 5) - 15.0 * num::pow(t, 4) + 10.0 * num::pow(t, 3)
+    6.0 * t.powi(5) - 15.0 * t.powi(4) + 10.0 * t.powi(3)
 }
 
 pub fn bilerp(x0y0: f64, x0y1: f64, x1y0: f64, x1y1: f64, x: f64, y: f64) -> f64 {
@@ -40,6 +40,10 @@ pub fn trilerp(v000: f64, v100: f64, v010: f64, v110: f64,
     t0+t1+t2+t3+t4+t5+t6+t7
 }
 
+pub fn blend_cubic(x: f64) -> f64 {
+    x*x*(3.0-(x*2.0))
+}
+
 pub fn blend_quintic(x: f64) -> f64 {
     x*x*x*(x*((x*6.0)-15.0)+10.0)
 }
@@ -57,55 +61,49 @@ pub fn clamp(n: f64, low: f64, high: f64) -> f64 {
     }
 }
 
-pub fn select_2d<L: NoiseGen, H: NoiseGen>(
-    control: f64, low_source: &mut L, high_source: &mut H,
-    threshold: f64, falloff: f64,
-    x: f64, y:f64) -> f64 {
-    
+// The blend between low and high for a control value, with a quintic falloff
+// either side of the threshold. Only the sources that are needed are called.
+// The edges are inclusive so a falloff of 0 is a hard step rather than NaN.
+pub(crate) fn select_with<L, H>(control: f64, threshold: f64, falloff: f64, low: L, high: H) -> f64
+    where L: FnOnce() -> f64, H: FnOnce() -> f64
+{
+    let upper = threshold + falloff;
+    let lower = threshold - falloff;
+
     match control {
-        n if n > (threshold + falloff) => {
-            high_source.get_value2d(x, y)
-        },
-        n if n < (threshold - falloff) => {
-            low_source.get_value2d(x, y)
-        },
+        n if n >= upper => high(),
+        n if n <= lower => low(),
         n => {
-            let upper = threshold + falloff;
-            let lower = threshold - falloff;
             let nn = (n-lower)/(upper-lower);
             let blend = blend_quintic(nn);
-            lerp(low_source.get_value2d(x, y),
-                 high_source.get_value2d(x, y),
-                 blend)
+            lerp(low(), high(), blend)
         }
     }
 }
 
+// gen::select::Select does the same as a generator, for any dimension
+pub fn select_2d<L: NoiseGen, H: NoiseGen>(
+    control: f64, low_source: &L, high_source: &H,
+    threshold: f64, falloff: f64,
+    x: f64, y:f64) -> f64 {
+
+    select_with(control, threshold, falloff,
+                || low_source.get_value2d(x, y),
+                || high_source.get_value2d(x, y))
+}
+
 pub fn select_3d<L: NoiseGen, H: NoiseGen>(
-    control: f64, low_source: &mut L, high_source: &mut H,
+    control: f64, low_source: &L, high_source: &H,
     threshold: f64, falloff: f64,
     x: f64, y: f64, z: f64) -> f64 {
 
-    match control {
-        n if n > (threshold + falloff) => {
-            high_source.get_value3d(x, y, z)
-        },
-        n if n < ( threshold - falloff) => {
-            low_source.get_value3d(x, y, z)
-        },
-        n => {
-            let upper = threshold + falloff;
-            let lower = threshold - falloff;
-            let nn = (n-lower)/(upper-lower);
-            let blend = blend_quintic(nn);
-
-            lerp(low_source.get_value3d(x, y, z),
-                 high_source.get_value3d(x, y, z),
-                 blend)
-        }
-    }
+    select_with(control, threshold, falloff,
+                || low_source.get_value3d(x, y, z),
+                || high_source.get_value3d(x, y, z))
 }
 
+// steps must not be empty. gen::modifier::Terrace checks its points and can
+// ease between them.
 pub fn step(n: f64, steps: &[f64]) -> f64 {
     let mut last_step = steps[0];
     for &s in steps.iter() {
@@ -118,9 +116,11 @@ pub fn step(n: f64, steps: &[f64]) -> f64 {
     last_step
 }
 
+// The falloff here is applied to the squared distance, so the blend narrows
+// as the radius grows. gen::radialmask::RadialMask measures it in distance.
 pub fn circle<I: NoiseGen, O: NoiseGen>(
     radius: f64, cx: f64, cy: f64, falloff: f64,
-    inside: &mut I, outside: &mut O,
+    inside: &I, outside: &O,
     x: f64, y: f64
     ) -> f64 {
     let dx = x - cx;
@@ -149,9 +149,10 @@ pub fn circle<I: NoiseGen, O: NoiseGen>(
     }
 }
 
+// Same squared falloff as circle
 pub fn sphere<I: NoiseGen, O: NoiseGen>(
     radius: f64, cx: f64, cy: f64, cz: f64, falloff: f64,
-    inside: &mut I, outside: &mut O,
+    inside: &I, outside: &O,
     x: f64, y: f64, z: f64
     ) -> f64 {
     let dx = x - cx;
@@ -0,0 +1,65 @@
+// builder.rs
+
+mod common;
+
+use noise::error::BuildError;
+use noise::gen::simplex::Simplex;
+use noise::gen::fbm::FBM;
+use noise::gen::billow::Billow;
+use noise::gen::ridgedmulti::RidgedMulti;
+use common::assert_same;
+
+#[test]
+fn builders_match_from_seed() {
+    let fbm = FBM::builder().seed(1).octaves(5).persistence(0.6).lacunarity(2.2).zoom(10.0).build().unwrap();
+    assert_same(&fbm, &FBM::from_seed(1, 5, 0.6, 2.2, 10.0));
+
+    let billow = Billow::builder().seed(2).octaves(5).persistence(0.6).lacunarity(2.2).zoom(10.0).build().unwrap();
+    assert_same(&billow, &Billow::from_seed(2, 5, 0.6, 2.2, 10.0));
+
+    let ridged = RidgedMulti::builder()
+        .seed(3)
+        .octaves(5)
+        .gain(1.7)
+        .lacunarity(1.9)
+        .offset(0.9)
+        .h(0.75)
+        .zoom(10.0)
+        .build()
+        .unwrap();
+    assert_same(&ridged, &RidgedMulti::from_seed(3, 5, 1.7, 1.9, 0.9, 0.75, 10.0));
+}
+
+#[test]
+fn unseeded_builds_keep_the_seed_together() {
+    let ngen = FBM::builder().build().unwrap();
+    assert_eq!(ngen.get_octave_seed(), ngen.get_seed());
+}
+
+#[test]
+fn build_with_source_seeds_the_offsets() {
+    let ngen = Billow::builder().seed(4).build_with_source(Simplex::from_seed(9, 10.0)).unwrap();
+    assert_eq!(ngen.get_octave_seed(), 4);
+    assert_eq!(ngen.source().get_seed(), 9);
+
+    let ngen = RidgedMulti::builder().build_with_source(Simplex::from_seed(9, 10.0)).unwrap();
+    assert_eq!(ngen.get_octave_seed(), 0);
+}
+
+#[test]
+fn builders_reject_bad_settings() {
+    assert_eq!(FBM::builder().octaves(0).build().err(), Some(BuildError::Octaves(0)));
+    assert_eq!(Billow::builder().octaves(-3).build().err(), Some(BuildError::Octaves(-3)));
+    assert_eq!(RidgedMulti::builder().zoom(0.0).build().err(), Some(BuildError::Zoom(0.0)));
+    assert_eq!(FBM::builder().lacunarity(0.0).build().err(), Some(BuildError::Lacunarity(0.0)));
+    assert_eq!(RidgedMulti::builder().lacunarity(-2.0).build().err(), Some(BuildError::Lacunarity(-2.0)));
+    assert!(matches!(Billow::builder().zoom(f64::NAN).build(), Err(BuildError::Zoom(_))));
+    assert!(matches!(FBM::builder().lacunarity(f64::NAN).build(), Err(BuildError::Lacunarity(_))));
+    assert!(FBM::builder().octaves(0).build_with_source(Simplex::from_seed(1, 1.0)).is_err());
+}
+
+#[test]
+fn errors_describe_the_setting() {
+    assert_eq!(BuildError::Octaves(0).to_string(), "octaves must be at least 1, got 0");
+    assert_eq!(BuildError::Zoom(0.0).to_string(), "zoom must be finite and non-zero, got 0");
+}
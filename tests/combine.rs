@@ -0,0 +1,77 @@
+// combine.rs
+
+mod common;
+
+use noise::gen::NoiseGen;
+use noise::gen::simplex::Simplex;
+use noise::gen::perlin::Perlin;
+use noise::gen::fbm::FBM;
+use noise::gen::ridgedmulti::RidgedMulti;
+use noise::gen::combine::{Add, Subtract, Multiply, Min, Max, Power, Blend};
+use common::{points, assert_close};
+
+fn assert_combines<T: NoiseGen>(ngen: &T, a: &Simplex, b: &Perlin, op: fn(f64, f64) -> f64) {
+    for (x, y, z, w) in points() {
+        assert_close(ngen.get_value1d(x), op(a.get_value1d(x), b.get_value1d(x)));
+        assert_close(ngen.get_value2d(x, y), op(a.get_value2d(x, y), b.get_value2d(x, y)));
+        assert_close(ngen.get_value3d(x, y, z), op(a.get_value3d(x, y, z), b.get_value3d(x, y, z)));
+        assert_close(ngen.get_value4d(x, y, z, w), op(a.get_value4d(x, y, z, w), b.get_value4d(x, y, z, w)));
+    }
+}
+
+#[test]
+fn combinators_apply_their_op() {
+    let a = Simplex::from_seed(1, 7.0);
+    let b = Perlin::from_seed(2, 5.0);
+    assert_combines(&Add::new(&a, &b), &a, &b, |a, b| a + b);
+    assert_combines(&Subtract::new(&a, &b), &a, &b, |a, b| a - b);
+    assert_combines(&Multiply::new(&a, &b), &a, &b, |a, b| a * b);
+    assert_combines(&Min::new(&a, &b), &a, &b, f64::min);
+    assert_combines(&Max::new(&a, &b), &a, &b, f64::max);
+    assert_combines(&Power::new(Add::new(&a, 1.0), &b), &a, &b, |a, b| (a + 1.0).powf(b));
+}
+
+#[test]
+fn blend_follows_the_control() {
+    let a = Simplex::from_seed(3, 7.0);
+    let b = Perlin::from_seed(4, 5.0);
+    assert_combines(&Blend::new(&a, &b, 0.0), &a, &b, |a, _| a);
+    assert_combines(&Blend::new(&a, &b, 1.0), &a, &b, |_, b| b);
+    assert_combines(&Blend::new(&a, &b, 0.25), &a, &b, |a, b| 0.75*a + 0.25*b);
+}
+
+#[test]
+fn operators_build_graphs() {
+    let fbm = FBM::from_seed(5, 4, 0.5, 2.0, 20.0);
+    let ridged = RidgedMulti::from_seed(6, 4, 1.7, 1.9, 1.0, 0.75, 20.0);
+    let explicit = Add::new(Multiply::new(&fbm, 0.5), &ridged);
+
+    let mut ngen = FBM::from_seed(5, 4, 0.5, 2.0, 20.0) * 0.5 + RidgedMulti::from_seed(6, 4, 1.7, 1.9, 1.0, 0.75, 20.0)
+        - Simplex::from_seed(7, 9.0) * 0.0;
+    for (x, y, z, w) in points() {
+        assert_eq!(ngen.get_value2d(x, y), explicit.get_value2d(x, y));
+        assert_eq!(ngen.get_value4d(x, y, z, w), explicit.get_value4d(x, y, z, w));
+    }
+
+    // the graph can still be reached into and changed
+    ngen.lhs_mut().rhs_mut().set_octaves(2).unwrap();
+    assert_eq!(ngen.lhs().rhs().get_octaves(), 2);
+}
+
+// Sources can be picked at runtime
+#[test]
+fn boxed_sources() {
+    let sources: Vec<Box<dyn NoiseGen>> = vec![Box::new(Simplex::from_seed(8, 7.0)), Box::new(Perlin::from_seed(8, 7.0)), Box::new(0.5)];
+    let ngen = sources.into_iter().reduce(|a, b| Box::new(Max::new(a, b))).unwrap();
+    let (s, p) = (Simplex::from_seed(8, 7.0), Perlin::from_seed(8, 7.0));
+    for (x, y, z, _) in points() {
+        assert_eq!(ngen.get_value3d(x, y, z), s.get_value3d(x, y, z).max(p.get_value3d(x, y, z)).max(0.5));
+    }
+
+    // fills go through to the boxed generator's own
+    let boxed: Box<dyn NoiseGen> = Box::new(Simplex::from_seed(9, 3.0));
+    let (mut a, mut b) = (vec![0.0; 37 * 11], vec![0.0; 37 * 11]);
+    boxed.fill_2d((-4.5, 2.25), 0.37, 37, 11, &mut a);
+    Simplex::from_seed(9, 3.0).fill_2d((-4.5, 2.25), 0.37, 37, 11, &mut b);
+    assert_eq!(a, b);
+}
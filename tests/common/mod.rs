@@ -0,0 +1,33 @@
+// common/mod.rs
+
+// Helpers shared by the integration tests. Every test file builds its own
+// copy of this module and most only use part of it.
+#![allow(dead_code)]
+
+use noise::gen::NoiseGen;
+
+// Checks two generators give exactly the same values in every dimension
+pub fn assert_same<A: NoiseGen, B: NoiseGen>(a: &A, b: &B) {
+    for i in 0..64 {
+        let x = (i as f64) * 0.37 - 11.0;
+        let y = (i as f64) * -0.21 + 4.0;
+        assert_eq!(a.get_value1d(x), b.get_value1d(x));
+        assert_eq!(a.get_value2d(x, y), b.get_value2d(x, y));
+        assert_eq!(a.get_value3d(x, y, 0.5*x), b.get_value3d(x, y, 0.5*x));
+        assert_eq!(a.get_value4d(x, y, 0.5*x, 0.5*y), b.get_value4d(x, y, 0.5*x, 0.5*y));
+    }
+}
+
+// Points along a line through 4D space that isn't parallel to any axis or
+// lattice diagonal, so they land all over the cells
+pub fn points() -> Vec<(f64, f64, f64, f64)> {
+    (0..300).map(|i| {
+        let t = (i as f64) * 0.731 - 110.0;
+        (t, 0.61*t + 2.3, -0.83*t + 0.7, 0.27*t - 5.1)
+    }).collect()
+}
+
+// For values that go through a lerp or another rounding step on one side only
+pub fn assert_close(a: f64, b: f64) {
+    assert!((a - b).abs() < 1e-12, "{} != {}", a, b);
+}
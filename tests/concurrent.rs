@@ -0,0 +1,93 @@
+// concurrent.rs
+
+use std::sync::Arc;
+use std::sync::mpsc::channel;
+use std::thread;
+use noise::gen::NoiseGen;
+use noise::gen::simplex::Simplex;
+use noise::gen::fbm::FBM;
+use noise::gen::billow::Billow;
+use noise::gen::ridgedmulti::RidgedMulti;
+use noise::gen::voronoi::Voronoi;
+use noise::gen::hybridmulti::HybridMulti;
+use noise::gen::heteroterrain::HeteroTerrain;
+use noise::gen::erosion::Erosion;
+use noise::gen::perlin::Perlin;
+use noise::gen::value::Value;
+use noise::gen::select::Select;
+use noise::gen::radialmask::RadialMask;
+use noise::gen::combine::{Add, Multiply};
+use noise::gen::modifier::{ScaleBias, Clamp, Terrace};
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+fn sample_row<T: NoiseGen>(ngen: &T) -> Vec<f64> {
+    (0..64).map(|i| {
+        let x = i as f64;
+        ngen.get_value2d(x, 0.5*x) + ngen.get_value3d(x, 0.5*x, 0.25*x)
+    }).collect()
+}
+
+fn sample_concurrently<T: NoiseGen + Send + Sync + 'static>(ngen: T) {
+    let ngen = Arc::new(ngen);
+    let expected = sample_row(&*ngen);
+
+    let (tx, rx) = channel();
+    for _ in 0..4 {
+        let ngen = ngen.clone();
+        let tx = tx.clone();
+        thread::spawn(move || {
+            tx.send(sample_row(&*ngen)).unwrap();
+        });
+    }
+
+    for _ in 0..4 {
+        assert_eq!(rx.recv().unwrap(), expected);
+    }
+}
+
+#[test]
+fn generators_are_send_and_sync() {
+    assert_send_sync::<Simplex>();
+    assert_send_sync::<FBM>();
+    assert_send_sync::<Billow>();
+    assert_send_sync::<RidgedMulti>();
+    assert_send_sync::<Voronoi>();
+    assert_send_sync::<FBM<Voronoi>>();
+    assert_send_sync::<HybridMulti>();
+    assert_send_sync::<HeteroTerrain>();
+    assert_send_sync::<Erosion>();
+    assert_send_sync::<Perlin>();
+    assert_send_sync::<FBM<Perlin>>();
+    assert_send_sync::<Value>();
+    assert_send_sync::<Select<FBM, Voronoi, Billow>>();
+    assert_send_sync::<RadialMask<RidgedMulti, f64>>();
+    assert_send_sync::<Add<Multiply<FBM, f64>, RidgedMulti>>();
+    assert_send_sync::<Clamp<ScaleBias<Billow>>>();
+    assert_send_sync::<Terrace<FBM>>();
+}
+
+#[test]
+fn simplex_concurrent() {
+    sample_concurrently(Simplex::from_seed(1, 10.0));
+}
+
+#[test]
+fn fbm_concurrent() {
+    sample_concurrently(FBM::from_seed(2, 8, 0.5, 2.0, 10.0));
+}
+
+#[test]
+fn billow_concurrent() {
+    sample_concurrently(Billow::from_seed(3, 8, 0.5, 2.0, 10.0));
+}
+
+#[test]
+fn ridgedmulti_concurrent() {
+    sample_concurrently(RidgedMulti::from_seed(4, 8, 1.7, 1.9, 1.0, 0.75, 10.0));
+}
+
+#[test]
+fn voronoi_concurrent() {
+    sample_concurrently(Voronoi::from_seed(5, 10.0));
+}
@@ -0,0 +1,157 @@
+// deriv.rs
+
+mod common;
+
+use noise::error::BuildError;
+use noise::gen::{NoiseGen, NoiseDeriv};
+use noise::gen::simplex::Simplex;
+use noise::gen::fbm::FBM;
+use noise::gen::erosion::Erosion;
+use common::points;
+
+const H: f64 = 1e-6;
+
+// Central differences have an error around H^2, plus rounding around 1e-16 / H
+fn assert_deriv(analytic: f64, numeric: f64) {
+    assert!((analytic - numeric).abs() < 1e-6 * (1.0 + analytic.abs()), "{} != {}", analytic, numeric);
+}
+
+#[test]
+fn simplex_deriv_values_match() {
+    let ngen = Simplex::from_seed(1, 3.7);
+    for (x, y, z, w) in points() {
+        assert_eq!(ngen.get_value1d_deriv(x).0, ngen.get_value1d(x));
+        assert_eq!(ngen.get_value2d_deriv(x, y).0, ngen.get_value2d(x, y));
+        assert_eq!(ngen.get_value3d_deriv(x, y, z).0, ngen.get_value3d(x, y, z));
+        assert_eq!(ngen.get_value4d_deriv(x, y, z, w).0, ngen.get_value4d(x, y, z, w));
+    }
+}
+
+#[test]
+fn simplex_1d_deriv_matches_finite_differences() {
+    let ngen = Simplex::from_seed(2, 3.7);
+    for (x, _, _, _) in points() {
+        let (_, dx) = ngen.get_value1d_deriv(x);
+        assert_deriv(dx, (ngen.get_value1d(x + H) - ngen.get_value1d(x - H)) / (2.0 * H));
+    }
+}
+
+#[test]
+fn simplex_2d_deriv_matches_finite_differences() {
+    let ngen = Simplex::from_seed(3, 3.7);
+    for (x, y, _, _) in points() {
+        let (_, dx, dy) = ngen.get_value2d_deriv(x, y);
+        assert_deriv(dx, (ngen.get_value2d(x + H, y) - ngen.get_value2d(x - H, y)) / (2.0 * H));
+        assert_deriv(dy, (ngen.get_value2d(x, y + H) - ngen.get_value2d(x, y - H)) / (2.0 * H));
+    }
+}
+
+#[test]
+fn simplex_3d_deriv_matches_finite_differences() {
+    let ngen = Simplex::from_seed(4, 3.7);
+    for (x, y, z, _) in points() {
+        let (_, dx, dy, dz) = ngen.get_value3d_deriv(x, y, z);
+        assert_deriv(dx, (ngen.get_value3d(x + H, y, z) - ngen.get_value3d(x - H, y, z)) / (2.0 * H));
+        assert_deriv(dy, (ngen.get_value3d(x, y + H, z) - ngen.get_value3d(x, y - H, z)) / (2.0 * H));
+        assert_deriv(dz, (ngen.get_value3d(x, y, z + H) - ngen.get_value3d(x, y, z - H)) / (2.0 * H));
+    }
+}
+
+#[test]
+fn simplex_4d_deriv_matches_finite_differences() {
+    let ngen = Simplex::from_seed(5, 3.7);
+    for (x, y, z, w) in points() {
+        let (_, dx, dy, dz, dw) = ngen.get_value4d_deriv(x, y, z, w);
+        assert_deriv(dx, (ngen.get_value4d(x + H, y, z, w) - ngen.get_value4d(x - H, y, z, w)) / (2.0 * H));
+        assert_deriv(dy, (ngen.get_value4d(x, y + H, z, w) - ngen.get_value4d(x, y - H, z, w)) / (2.0 * H));
+        assert_deriv(dz, (ngen.get_value4d(x, y, z + H, w) - ngen.get_value4d(x, y, z - H, w)) / (2.0 * H));
+        assert_deriv(dw, (ngen.get_value4d(x, y, z, w + H) - ngen.get_value4d(x, y, z, w - H)) / (2.0 * H));
+    }
+}
+
+#[test]
+fn fbm_deriv_values_match() {
+    let ngen = FBM::from_seed(9, 6, 0.5, 2.0, 37.0);
+    for (x, y, z, w) in points() {
+        assert_eq!(ngen.get_value1d_deriv(x).0, ngen.get_value1d(x));
+        assert_eq!(ngen.get_value2d_deriv(x, y).0, ngen.get_value2d(x, y));
+        assert_eq!(ngen.get_value3d_deriv(x, y, z).0, ngen.get_value3d(x, y, z));
+        assert_eq!(ngen.get_value4d_deriv(x, y, z, w).0, ngen.get_value4d(x, y, z, w));
+    }
+}
+
+// Zoomed out so the highest octave still changes slowly over H
+#[test]
+fn fbm_deriv_matches_finite_differences() {
+    let ngen = FBM::from_seed(10, 6, 0.5, 2.0, 37.0);
+    for (x, y, z, w) in points() {
+        let (_, dx) = ngen.get_value1d_deriv(x);
+        assert_deriv(dx, (ngen.get_value1d(x + H) - ngen.get_value1d(x - H)) / (2.0 * H));
+
+        let (_, dx, dy) = ngen.get_value2d_deriv(x, y);
+        assert_deriv(dx, (ngen.get_value2d(x + H, y) - ngen.get_value2d(x - H, y)) / (2.0 * H));
+        assert_deriv(dy, (ngen.get_value2d(x, y + H) - ngen.get_value2d(x, y - H)) / (2.0 * H));
+
+        let (_, dx, dy, dz) = ngen.get_value3d_deriv(x, y, z);
+        assert_deriv(dx, (ngen.get_value3d(x + H, y, z) - ngen.get_value3d(x - H, y, z)) / (2.0 * H));
+        assert_deriv(dy, (ngen.get_value3d(x, y + H, z) - ngen.get_value3d(x, y - H, z)) / (2.0 * H));
+        assert_deriv(dz, (ngen.get_value3d(x, y, z + H) - ngen.get_value3d(x, y, z - H)) / (2.0 * H));
+
+        let (_, dx, dy, dz, dw) = ngen.get_value4d_deriv(x, y, z, w);
+        assert_deriv(dx, (ngen.get_value4d(x + H, y, z, w) - ngen.get_value4d(x - H, y, z, w)) / (2.0 * H));
+        assert_deriv(dy, (ngen.get_value4d(x, y + H, z, w) - ngen.get_value4d(x, y - H, z, w)) / (2.0 * H));
+        assert_deriv(dz, (ngen.get_value4d(x, y, z + H, w) - ngen.get_value4d(x, y, z - H, w)) / (2.0 * H));
+        assert_deriv(dw, (ngen.get_value4d(x, y, z, w + H) - ngen.get_value4d(x, y, z, w - H)) / (2.0 * H));
+    }
+}
+
+// Derivatives of a generic source go through the same chain rule
+#[test]
+fn nested_fbm_deriv_matches_finite_differences() {
+    let ngen = FBM::from_source(FBM::from_seed(11, 3, 0.5, 2.0, 37.0), 3, 0.5, 2.0);
+    for (x, y, _, _) in points() {
+        let (_, dx, dy) = ngen.get_value2d_deriv(x, y);
+        assert_deriv(dx, (ngen.get_value2d(x + H, y) - ngen.get_value2d(x - H, y)) / (2.0 * H));
+        assert_deriv(dy, (ngen.get_value2d(x, y + H) - ngen.get_value2d(x, y - H)) / (2.0 * H));
+    }
+}
+
+// One octave is the simplex value damped by its own slope in lattice units
+#[test]
+fn erosion_damps_by_the_gradient() {
+    let simp = Simplex::from_seed(6, 1.0);
+    let ngen = Erosion::from_seed(6, 1, 0.5, 2.0, 20.0);
+    for (x, y, _, _) in points() {
+        let (v, dx, dy) = simp.get_value2d_deriv(x / 20.0, y / 20.0);
+        let n = (v / (1.0 + dx*dx + dy*dy) + 1.0) * 0.5;
+        assert!((ngen.get_value2d(x, y) - n).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn erosion_range() {
+    let ngen = Erosion::from_seed(7, 8, 0.5, 2.0, 20.0);
+    for (x, y, z, w) in points() {
+        for n in [ngen.get_value1d(x), ngen.get_value2d(x, y), ngen.get_value3d(x, y, z), ngen.get_value4d(x, y, z, w)].iter() {
+            assert!((0.0..=1.0).contains(n), "{} out of range", n);
+        }
+    }
+}
+
+#[test]
+fn erosion_builder_and_setters() {
+    let built = Erosion::builder().seed(8).octaves(5).persistence(0.6).lacunarity(2.2).zoom(10.0).build().unwrap();
+    let mut set = Erosion::from_seed(8, 2, 0.3, 1.5, 4.0);
+    set.set_octaves(5).unwrap();
+    set.set_persistence(0.6);
+    set.set_lacunarity(2.2).unwrap();
+    set.set_zoom(10.0).unwrap();
+    let new = Erosion::from_seed(8, 5, 0.6, 2.2, 10.0);
+    for (x, y, z, _) in points() {
+        assert_eq!(built.get_value2d(x, y), new.get_value2d(x, y));
+        assert_eq!(built.get_value3d(x, y, z), set.get_value3d(x, y, z));
+    }
+
+    assert_eq!(Erosion::builder().octaves(0).build().err(), Some(BuildError::Octaves(0)));
+    assert_eq!(Erosion::builder().zoom(0.0).build().err(), Some(BuildError::Zoom(0.0)));
+}
@@ -0,0 +1,151 @@
+// fill.rs
+
+use noise::gen::NoiseGen;
+use noise::gen::simplex::Simplex;
+use noise::gen::fbm::FBM;
+use noise::gen::billow::Billow;
+use noise::gen::ridgedmulti::RidgedMulti;
+use noise::gen::voronoi::Voronoi;
+use noise::gen::hybridmulti::HybridMulti;
+use noise::gen::heteroterrain::HeteroTerrain;
+
+const ORIGIN: (f64, f64, f64) = (-13.5, 7.25, 3.0);
+const STEP: f64 = 0.75;
+const WIDTH: usize = 17;
+const HEIGHT: usize = 11;
+const DEPTH: usize = 5;
+
+// Fills scale whole octaves at once, so allow for rounding in the coordinates
+fn assert_close(a: &[f64], b: &[f64]) {
+    assert_eq!(a.len(), b.len());
+    for (x, y) in a.iter().zip(b.iter()) {
+        assert!((x - y).abs() < 1e-9, "{} != {}", x, y);
+    }
+}
+
+fn check_fill_2d<T: NoiseGen>(ngen: &T) {
+    let mut out = vec![0.0; WIDTH * HEIGHT];
+    ngen.fill_2d((ORIGIN.0, ORIGIN.1), STEP, WIDTH, HEIGHT, &mut out);
+
+    let mut expected = Vec::new();
+    for j in 0..HEIGHT {
+        for i in 0..WIDTH {
+            expected.push(ngen.get_value2d(ORIGIN.0 + (i as f64) * STEP, ORIGIN.1 + (j as f64) * STEP));
+        }
+    }
+    assert_close(&out, &expected);
+}
+
+fn check_fill_3d<T: NoiseGen>(ngen: &T) {
+    let mut out = vec![0.0; WIDTH * HEIGHT * DEPTH];
+    ngen.fill_3d(ORIGIN, STEP, WIDTH, HEIGHT, DEPTH, &mut out);
+
+    let mut expected = Vec::new();
+    for k in 0..DEPTH {
+        for j in 0..HEIGHT {
+            for i in 0..WIDTH {
+                expected.push(ngen.get_value3d(ORIGIN.0 + (i as f64) * STEP,
+                                               ORIGIN.1 + (j as f64) * STEP,
+                                               ORIGIN.2 + (k as f64) * STEP));
+            }
+        }
+    }
+    assert_close(&out, &expected);
+}
+
+#[test]
+fn simplex_fill_matches_get_value() {
+    let ngen = Simplex::from_seed(1, 3.0);
+    check_fill_2d(&ngen);
+    check_fill_3d(&ngen);
+}
+
+// Simplex fills go through the batched kernel, which must agree exactly with
+// the scalar one. Cover every region over a width that isn't a whole number of
+// lanes, and points far enough out that the batched kernel hands them back.
+#[test]
+fn simplex_fill_2d_is_exact() {
+    let ngen = Simplex::from_seed(8, 1.0);
+    let (width, height) = (67, 41);
+
+    for &(ox, oy, step) in [(-20.3, -11.7, 0.37), (3.0e9, -3.0e9, 0.37)].iter() {
+        let mut out = vec![0.0; width * height];
+        ngen.fill_2d((ox, oy), step, width, height, &mut out);
+
+        for j in 0..height {
+            for i in 0..width {
+                let n = ngen.get_value2d(ox + (i as f64) * step, oy + (j as f64) * step);
+                assert_eq!(out[j * width + i], n);
+            }
+        }
+    }
+}
+
+#[test]
+fn fbm_fill_matches_get_value() {
+    let ngen = FBM::from_seed(2, 6, 0.5, 2.0, 3.0);
+    check_fill_2d(&ngen);
+    check_fill_3d(&ngen);
+}
+
+#[test]
+fn billow_fill_matches_get_value() {
+    let ngen = Billow::from_seed(3, 6, 0.5, 2.0, 3.0);
+    check_fill_2d(&ngen);
+    check_fill_3d(&ngen);
+}
+
+#[test]
+fn ridgedmulti_fill_matches_get_value() {
+    let ngen = RidgedMulti::from_seed(4, 6, 1.7, 1.9, 1.0, 0.75, 3.0);
+    check_fill_2d(&ngen);
+    check_fill_3d(&ngen);
+}
+
+#[test]
+fn hybridmulti_fill_matches_get_value() {
+    let ngen = HybridMulti::from_seed(10, 6, 2.0, 0.7, 0.25, 3.0);
+    check_fill_2d(&ngen);
+    check_fill_3d(&ngen);
+}
+
+#[test]
+fn heteroterrain_fill_matches_get_value() {
+    let ngen = HeteroTerrain::from_seed(11, 6, 2.0, 0.7, 0.25, 3.0);
+    check_fill_2d(&ngen);
+    check_fill_3d(&ngen);
+}
+
+#[test]
+fn default_fill_matches_get_value() {
+    let ngen = Voronoi::from_seed(5, 3.0);
+    check_fill_2d(&ngen);
+    check_fill_3d(&ngen);
+}
+
+#[test]
+fn fill_leaves_extra_buffer_untouched() {
+    let ngen = Simplex::from_seed(6, 3.0);
+    let mut out = vec![42.0; WIDTH * HEIGHT + 3];
+    ngen.fill_2d((0.0, 0.0), 1.0, WIDTH, HEIGHT, &mut out);
+    assert_eq!(&out[WIDTH * HEIGHT..], &[42.0, 42.0, 42.0]);
+}
+
+#[test]
+#[should_panic]
+fn fill_panics_on_short_buffer() {
+    let ngen = Simplex::from_seed(7, 3.0);
+    let mut out = vec![0.0; WIDTH * HEIGHT - 1];
+    ngen.fill_2d((0.0, 0.0), 1.0, WIDTH, HEIGHT, &mut out);
+}
+
+#[test]
+fn generic_source_fill_matches_get_value() {
+    let ngen = FBM::from_source(Billow::from_seed(8, 3, 0.5, 2.0, 3.0), 4, 0.5, 2.0);
+    check_fill_2d(&ngen);
+    check_fill_3d(&ngen);
+
+    let ngen = RidgedMulti::from_source(FBM::from_seed(9, 3, 0.5, 2.0, 3.0), 4, 1.7, 1.9, 1.0, 0.75);
+    check_fill_2d(&ngen);
+    check_fill_3d(&ngen);
+}
@@ -0,0 +1,121 @@
+// modifier.rs
+
+mod common;
+
+use noise::gen::NoiseGen;
+use noise::gen::simplex::Simplex;
+use noise::gen::fbm::FBM;
+use noise::error::BuildError;
+use noise::gen::modifier::{ScaleBias, Abs, Invert, Clamp, Exponent, Remap, Terrace};
+use common::points;
+
+fn assert_modifies<T: NoiseGen>(ngen: &T, source: &Simplex, op: &dyn Fn(f64) -> f64) {
+    for (x, y, z, w) in points() {
+        assert_eq!(ngen.get_value1d(x), op(source.get_value1d(x)));
+        assert_eq!(ngen.get_value2d(x, y), op(source.get_value2d(x, y)));
+        assert_eq!(ngen.get_value3d(x, y, z), op(source.get_value3d(x, y, z)));
+        assert_eq!(ngen.get_value4d(x, y, z, w), op(source.get_value4d(x, y, z, w)));
+    }
+}
+
+#[test]
+fn modifiers_apply_their_op() {
+    let s = Simplex::from_seed(1, 7.0);
+    assert_modifies(&ScaleBias::new(&s, 0.5, 0.25), &s, &|v| v * 0.5 + 0.25);
+    assert_modifies(&Abs::new(&s), &s, &|v| v.abs());
+    assert_modifies(&Invert::new(&s), &s, &|v| -v);
+    assert_modifies(&Clamp::new(&s, -0.2, 0.3), &s, &|v| v.clamp(-0.2, 0.3));
+    assert_modifies(&Exponent::new(&s, 2.0), &s, &|v| ((v + 1.0) * 0.5).powf(2.0) * 2.0 - 1.0);
+    assert_modifies(&Remap::new(&s, (-1.0, 1.0), (10.0, 20.0)), &s, &|v| (v + 1.0) * 5.0 + 10.0);
+}
+
+#[test]
+fn exponent_keeps_the_range() {
+    let ngen = Exponent::new(Simplex::from_seed(2, 7.0), 3.0);
+    assert_eq!(Exponent::new(-1.0, 3.0).get_value1d(0.0), -1.0);
+    assert_eq!(Exponent::new(1.0, 3.0).get_value1d(0.0), 1.0);
+    for (x, y, z, _) in points() {
+        assert!((-1.0..=1.0).contains(&ngen.get_value3d(x, y, z)));
+    }
+}
+
+#[test]
+fn remap_moves_fractals_into_simplex_range() {
+    let mut ngen = Remap::new(FBM::from_seed(3, 4, 0.5, 2.0, 10.0), (0.0, 1.0), (-1.0, 1.0));
+    for (x, y, _, _) in points() {
+        let n = ngen.source().get_value2d(x, y);
+        assert!((ngen.get_value2d(x, y) - (2.0*n - 1.0)).abs() < 1e-12);
+    }
+
+    ngen.set_to((1.0, -1.0));
+    ngen.source_mut().set_octaves(2).unwrap();
+    assert_eq!((ngen.get_from(), ngen.get_to()), ((0.0, 1.0), (1.0, -1.0)));
+    for (x, y, _, _) in points() {
+        let n = ngen.source().get_value2d(x, y);
+        assert!((ngen.get_value2d(x, y) - (1.0 - 2.0*n)).abs() < 1e-12);
+    }
+}
+
+// Modifiers and combinators mix freely
+#[test]
+fn modifiers_chain() {
+    let s = Simplex::from_seed(4, 7.0);
+    let ngen = Clamp::new(-Abs::new(Simplex::from_seed(4, 7.0)) * 2.0 + 0.5, -1.0, 1.0);
+    assert_eq!(ngen.get_bounds(), (-1.0, 1.0));
+    assert_modifies(&ngen, &s, &|v| (-v.abs() * 2.0 + 0.5).clamp(-1.0, 1.0));
+}
+
+#[test]
+fn terrace_shapes() {
+    let points = [-0.5, 0.0, 0.5];
+    let at = |ngen: &Terrace<f64>| ngen.get_value2d(0.0, 0.0);
+    let mut ngen = Terrace::new(0.25, &points).unwrap();
+
+    // Eased from the lower point, or from the upper one inverted
+    assert_eq!(at(&ngen), 0.125);
+    ngen.set_inverted(true);
+    assert_eq!(at(&ngen), 0.375);
+
+    // Hard steps go to the lower point, or the upper one inverted
+    ngen.set_smooth(false);
+    assert_eq!(at(&ngen), 0.5);
+    ngen.set_inverted(false);
+    assert_eq!(at(&ngen), 0.0);
+
+    // Points map to themselves and values outside them are clamped
+    for smooth in [true, false] {
+        for inverted in [true, false] {
+            ngen.set_smooth(smooth);
+            ngen.set_inverted(inverted);
+            for (v, expect) in [(-0.5, -0.5), (0.0, 0.0), (0.5, 0.5), (-3.0, -0.5), (2.0, 0.5)] {
+                *ngen.source_mut() = v;
+                assert_eq!(at(&ngen), expect);
+            }
+        }
+    }
+}
+
+#[test]
+fn terrace_matches_step() {
+    let points = [-1.0, -0.4, 0.1, 0.3, 1.0];
+    let mut ngen = Terrace::new(Simplex::from_seed(5, 7.0), &points).unwrap();
+    ngen.set_smooth(false);
+    assert_modifies(&ngen, &Simplex::from_seed(5, 7.0), &|v| noise::utils::step(v, &points));
+}
+
+#[test]
+fn terrace_rejects_bad_points() {
+    assert_eq!(Terrace::new(0.0, &[]).err(), Some(BuildError::TerraceCount(0)));
+    assert_eq!(Terrace::new(0.0, &[0.5]).err(), Some(BuildError::TerraceCount(1)));
+    assert_eq!(Terrace::new(0.0, &[0.0, 0.5, 0.5]).err(), Some(BuildError::TerracePoint(2, 0.5)));
+    assert_eq!(Terrace::new(0.0, &[0.0, -0.5]).err(), Some(BuildError::TerracePoint(1, -0.5)));
+    assert_eq!(Terrace::new(0.0, &[0.0, f64::INFINITY]).err(), Some(BuildError::TerracePoint(1, f64::INFINITY)));
+    assert!(matches!(Terrace::new(0.0, &[f64::NAN, 1.0]).err(), Some(BuildError::TerracePoint(0, _))));
+
+    // A rejected set leaves the points as they were
+    let mut ngen = Terrace::new(0.0, &[0.0, 1.0]).unwrap();
+    assert!(ngen.set_points(&[1.0, 0.0]).is_err());
+    assert_eq!(ngen.get_points(), &[0.0, 1.0]);
+    ngen.set_points(&[-1.0, 0.0, 1.0]).unwrap();
+    assert_eq!(ngen.get_points(), &[-1.0, 0.0, 1.0]);
+}
@@ -0,0 +1,62 @@
+// octaves.rs
+
+use noise::gen::NoiseGen;
+use noise::gen::simplex::Simplex;
+use noise::gen::fbm::FBM;
+use noise::gen::billow::Billow;
+use noise::gen::ridgedmulti::RidgedMulti;
+
+// The first octave isn't offset, and the next takes the first four draws of
+// the LCG, so a two octave FBM can be rebuilt by hand. The offsets are in the
+// fractal's coordinates at any zoom, so at large zooms they shrink to about a
+// lattice cell of the source.
+#[test]
+fn offsets_follow_the_documented_mapping() {
+    let mut prev: u32 = 7;
+    let mut o = [0.0; 4];
+    for a in o.iter_mut() {
+        prev = prev.wrapping_mul(1664525).wrapping_add(1013904223);
+        *a = (prev as f64) / 4294967296.0 * 1024.0;
+    }
+
+    for zoom in [10.0, 1000.0] {
+        let simp = Simplex::from_seed(7, zoom);
+        let ngen = FBM::from_seed(7, 2, 0.5, 2.0, zoom);
+        for i in 0..32 {
+            let x = (i as f64) * 1.3 * zoom - 20.0;
+            let y = (i as f64) * 0.7 * zoom;
+            let n = simp.get_value2d(x, y) + 0.5 * simp.get_value2d(2.0*x + o[0], 2.0*y + o[1]);
+            assert!((ngen.get_value2d(x, y) - (n / 1.5 + 1.0) * 0.5).abs() < 1e-12);
+        }
+    }
+}
+
+// Without offsets every octave of simplex is zero at the origin
+#[test]
+fn octaves_dont_share_the_origin() {
+    assert!(FBM::from_seed(1, 8, 0.5, 2.0, 10.0).get_value2d(0.0, 0.0) != 0.5);
+    assert!(Billow::from_seed(2, 8, 0.5, 2.0, 10.0).get_value3d(0.0, 0.0, 0.0) != 0.0);
+    assert!(RidgedMulti::from_seed(3, 8, 1.7, 1.9, 1.0, 0.75, 10.0).get_value2d(0.0, 0.0) != 1.0);
+}
+
+#[test]
+fn octave_seed_is_deterministic() {
+    let a = FBM::from_source(Simplex::from_seed(4, 10.0), 6, 0.5, 2.0);
+    let b = FBM::from_source(Simplex::from_seed(4, 10.0), 6, 0.5, 2.0);
+    let mut c = FBM::from_source(Simplex::from_seed(4, 10.0), 6, 0.5, 2.0);
+    c.set_octave_seed(5);
+
+    assert_eq!(a.get_octave_seed(), 0);
+    assert_eq!(c.get_octave_seed(), 5);
+    for i in 0..32 {
+        let x = (i as f64) * 1.3;
+        assert_eq!(a.get_value2d(x, -x), b.get_value2d(x, -x));
+        assert!(a.get_value2d(x, -x) != c.get_value2d(x, -x));
+    }
+}
+
+#[test]
+fn new_rand_uses_the_simplex_seed() {
+    let ngen = RidgedMulti::new_rand(6, 1.7, 1.9, 1.0, 0.75, 10.0);
+    assert_eq!(ngen.get_octave_seed(), ngen.get_seed());
+}
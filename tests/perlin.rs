@@ -0,0 +1,96 @@
+// perlin.rs
+
+mod common;
+
+use noise::gen::NoiseGen;
+use noise::gen::perlin::Perlin;
+use common::points;
+
+// Every gradient is dotted with a zero offset at its own lattice point
+#[test]
+fn zero_on_the_lattice() {
+    let ngen = Perlin::from_seed(1, 1.0);
+    for i in -20..20 {
+        let (x, y, z, w) = (i as f64, (3 - i) as f64, (i * 7 % 11) as f64, (i / 3) as f64);
+        assert_eq!(ngen.get_value1d(x), 0.0);
+        assert_eq!(ngen.get_value2d(x, y), 0.0);
+        assert_eq!(ngen.get_value3d(x, y, z), 0.0);
+        assert_eq!(ngen.get_value4d(x, y, z, w), 0.0);
+    }
+}
+
+// The peaks are narrow, so this samples every cell of a small block densely
+// rather than scattering points
+#[test]
+fn perlin_range() {
+    let ngen = Perlin::from_seed(2, 1.0);
+    let grid = |n: usize, per_cell: usize| (0..n * per_cell).map(move |i| (i as f64) / (per_cell as f64));
+
+    let max_1d = grid(64, 64).map(|x| ngen.get_value1d(x).abs()).fold(0.0, f64::max);
+    let mut max_2d: f64 = 0.0;
+    for x in grid(16, 32) {
+        for y in grid(16, 32) {
+            max_2d = max_2d.max(ngen.get_value2d(x, y).abs());
+        }
+    }
+    let mut max_3d: f64 = 0.0;
+    for x in grid(6, 16) {
+        for y in grid(6, 16) {
+            for z in grid(6, 16) {
+                max_3d = max_3d.max(ngen.get_value3d(x, y, z).abs());
+            }
+        }
+    }
+    let mut max_4d: f64 = 0.0;
+    for x in grid(3, 8) {
+        for y in grid(3, 8) {
+            for z in grid(3, 8) {
+                for w in grid(3, 8) {
+                    max_4d = max_4d.max(ngen.get_value4d(x, y, z, w).abs());
+                }
+            }
+        }
+    }
+
+    assert!(max_1d <= 1.0 && max_2d <= 1.0, "{} {}", max_1d, max_2d);
+    assert!(max_3d <= 1.04, "{}", max_3d);
+    assert!(max_4d <= 1.15, "{}", max_4d);
+    // A grid that missed the peaks would pass the bounds trivially
+    assert!(max_3d > 0.8 && max_4d > 0.8, "{} {}", max_3d, max_4d);
+}
+
+// Neighbouring lattice cells must agree on their shared faces
+#[test]
+fn perlin_is_continuous() {
+    let ngen = Perlin::from_seed(3, 1.0);
+    let e = 1e-9;
+    for i in -10..10 {
+        let c = i as f64;
+        let (y, z, w) = (0.3 * c + 0.1, 0.7 - 0.2 * c, 0.45);
+        assert!((ngen.get_value2d(c - e, y) - ngen.get_value2d(c + e, y)).abs() < 1e-8);
+        assert!((ngen.get_value3d(y, c - e, z) - ngen.get_value3d(y, c + e, z)).abs() < 1e-8);
+        assert!((ngen.get_value4d(y, z, w, c - e) - ngen.get_value4d(y, z, w, c + e)).abs() < 1e-8);
+    }
+}
+
+#[test]
+fn perlin_setters_match_new() {
+    let mut ngen = Perlin::from_seed(4, 10.0);
+    ngen.set_seed(5);
+    ngen.set_zoom(2.5);
+    assert_eq!((ngen.get_seed(), ngen.get_zoom()), (5, 2.5));
+
+    let new = Perlin::from_seed(5, 2.5);
+    let other = Perlin::from_seed(6, 2.5);
+    let mut differ = 0;
+    for (x, y, z, w) in points() {
+        assert_eq!(ngen.get_value1d(x), new.get_value1d(x));
+        assert_eq!(ngen.get_value2d(x, y), new.get_value2d(x, y));
+        assert_eq!(ngen.get_value3d(x, y, z), new.get_value3d(x, y, z));
+        assert_eq!(ngen.get_value4d(x, y, z, w), new.get_value4d(x, y, z, w));
+        if ngen.get_value3d(x, y, z) != other.get_value3d(x, y, z) {
+            differ += 1;
+        }
+    }
+    assert!(differ > points().len() * 4 / 5);
+}
@@ -0,0 +1,64 @@
+// radialmask.rs
+
+use noise::gen::NoiseGen;
+use noise::gen::fbm::FBM;
+use noise::gen::radialmask::RadialMask;
+
+fn mask() -> RadialMask<f64, f64> {
+    let mut ngen = RadialMask::new(1.0, -1.0, 10.0, 2.0);
+    ngen.set_center([3.0, -4.0, 5.0, 1.0]);
+    ngen
+}
+
+// Points at distance d from the center along a diagonal, in each dimension
+fn at(ngen: &RadialMask<f64, f64>, d: f64) -> [f64; 4] {
+    let c = ngen.get_center();
+    [ngen.get_value1d(c[0] + d),
+     ngen.get_value2d(c[0] + d / 2f64.sqrt(), c[1] - d / 2f64.sqrt()),
+     ngen.get_value3d(c[0] + d / 3f64.sqrt(), c[1] + d / 3f64.sqrt(), c[2] - d / 3f64.sqrt()),
+     ngen.get_value4d(c[0] + d / 2.0, c[1] - d / 2.0, c[2] + d / 2.0, c[3] + d / 2.0)]
+}
+
+fn assert_all(a: [f64; 4], n: f64) {
+    for v in a.iter() {
+        assert!((v - n).abs() < 1e-12, "{:?} != {}", a, n);
+    }
+}
+
+// Every dimension measures the same distance from the center
+#[test]
+fn same_mask_in_every_dimension() {
+    let ngen = mask();
+    assert_all(at(&ngen, 0.0), 1.0);
+    assert_all(at(&ngen, 7.9), 1.0);
+    assert_all(at(&ngen, 10.0), 0.0);
+    assert_all(at(&ngen, 12.1), -1.0);
+    assert_all(at(&ngen, 50.0), -1.0);
+
+    // the quintic blend is symmetric about the radius
+    let (a, b) = (at(&ngen, 9.0), at(&ngen, 11.0));
+    for i in 0..4 {
+        assert!((a[i] + b[i]).abs() < 1e-12);
+    }
+}
+
+// The falloff is a distance, so a bigger radius doesn't change the band
+#[test]
+fn falloff_does_not_depend_on_radius() {
+    let mut ngen = mask();
+    let small = at(&ngen, 9.5);
+    ngen.set_radius(1000.0);
+    assert_all(at(&ngen, 999.5), small[0]);
+    assert_all(at(&ngen, 997.9), 1.0);
+    assert_all(at(&ngen, 1002.1), -1.0);
+}
+
+#[test]
+fn radialmask_nests() {
+    let inner = RadialMask::new(FBM::from_seed(1, 4, 0.5, 2.0, 10.0), 0.0, 20.0, 5.0);
+    let mut ngen = FBM::from_source(RadialMask::new(inner, 0.5, 40.0, 5.0), 2, 0.5, 2.0);
+    ngen.source_mut().inside_mut().set_falloff(0.0);
+    assert_eq!(ngen.source().inside().get_falloff(), 0.0);
+    assert!((ngen.source().get_value2d(30.0, 0.0)).abs() < 1e-12);
+    assert_eq!(ngen.source().get_value3d(0.0, 0.0, 100.0), 0.5);
+}
@@ -0,0 +1,74 @@
+// region.rs
+
+use noise::gen::NoiseGen;
+use noise::gen::simplex::Simplex;
+use noise::gen::fbm::FBM;
+use noise::gen::voronoi::Voronoi;
+use noise::region::{Region2D, Region3D};
+#[cfg(feature = "rayon")]
+use noise::gen::ridgedmulti::RidgedMulti;
+
+// Sizes that don't divide evenly into bands, so the last band is short
+const WIDTH: usize = 37;
+const HEIGHT: usize = 23;
+const DEPTH: usize = 3;
+
+#[test]
+fn build_2d_matches_fill_in_one_band() {
+    let ngen = FBM::from_seed(1, 6, 0.5, 2.0, 3.0);
+    let region = Region2D::new((-4.5, 2.25), 0.5, WIDTH, HEIGHT).chunk_rows(HEIGHT);
+
+    let mut expected = vec![0.0; WIDTH * HEIGHT];
+    ngen.fill_2d((-4.5, 2.25), 0.5, WIDTH, HEIGHT, &mut expected);
+    assert_eq!(region.build(&ngen), expected);
+}
+
+#[test]
+fn build_3d_matches_get_value() {
+    let ngen = Voronoi::from_seed(2, 3.0);
+    let out = Region3D::new((1.0, -2.0, 0.5), 0.75, WIDTH, HEIGHT, DEPTH).chunk_rows(5).build(&ngen);
+
+    for k in 0..DEPTH {
+        for j in 0..HEIGHT {
+            for i in 0..WIDTH {
+                let n = ngen.get_value3d(1.0 + (i as f64) * 0.75, -2.0 + (j as f64) * 0.75, 0.5 + (k as f64) * 0.75);
+                assert_eq!(out[(k * HEIGHT + j) * WIDTH + i], n);
+            }
+        }
+    }
+}
+
+#[test]
+fn empty_region_builds_nothing() {
+    let ngen = Simplex::from_seed(3, 3.0);
+    assert!(Region2D::new((0.0, 0.0), 1.0, 0, HEIGHT).build(&ngen).is_empty());
+    assert!(Region3D::new((0.0, 0.0, 0.0), 1.0, WIDTH, HEIGHT, 0).build(&ngen).is_empty());
+}
+
+#[test]
+#[should_panic]
+fn build_into_panics_on_short_buffer() {
+    let ngen = Simplex::from_seed(4, 3.0);
+    let mut out = vec![0.0; WIDTH * HEIGHT - 1];
+    Region2D::new((0.0, 0.0), 1.0, WIDTH, HEIGHT).build_into(&ngen, &mut out);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn build_par_2d_matches_build() {
+    let ngen = RidgedMulti::from_seed(5, 12, 1.7, 1.9, 1.0, 0.75, 10.0);
+    for &rows in [1, 4, 16, HEIGHT].iter() {
+        let region = Region2D::new((-13.5, 7.25), 0.75, WIDTH, HEIGHT).chunk_rows(rows);
+        assert_eq!(region.build_par(&ngen), region.build(&ngen));
+    }
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn build_par_3d_matches_build() {
+    let ngen = FBM::from_seed(6, 8, 0.5, 2.0, 10.0);
+    for &rows in [1, 7, HEIGHT].iter() {
+        let region = Region3D::new((-13.5, 7.25, 3.0), 0.75, WIDTH, HEIGHT, DEPTH).chunk_rows(rows);
+        assert_eq!(region.build_par(&ngen), region.build(&ngen));
+    }
+}
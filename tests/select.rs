@@ -0,0 +1,70 @@
+// select.rs
+
+use noise::gen::NoiseGen;
+use noise::gen::fbm::FBM;
+use noise::gen::billow::Billow;
+use noise::gen::ridgedmulti::RidgedMulti;
+use noise::gen::select::Select;
+use noise::utils::{select_2d, select_3d};
+
+// Fails the test if it's ever sampled
+struct Unused;
+
+impl NoiseGen for Unused {
+    fn get_value1d(&self, _: f64) -> f64 { panic!("sampled") }
+    fn get_value2d(&self, _: f64, _: f64) -> f64 { panic!("sampled") }
+    fn get_value3d(&self, _: f64, _: f64, _: f64) -> f64 { panic!("sampled") }
+    fn get_value4d(&self, _: f64, _: f64, _: f64, _: f64) -> f64 { panic!("sampled") }
+}
+
+#[test]
+fn select_matches_utils() {
+    let control = FBM::from_seed(1, 4, 0.5, 2.0, 40.0);
+    let low = RidgedMulti::from_seed(2, 4, 1.7, 1.9, 1.0, 0.75, 10.0);
+    let high = Billow::from_seed(3, 4, 0.5, 2.0, 10.0);
+    let ngen = Select::new(FBM::from_seed(1, 4, 0.5, 2.0, 40.0),
+                           RidgedMulti::from_seed(2, 4, 1.7, 1.9, 1.0, 0.75, 10.0),
+                           Billow::from_seed(3, 4, 0.5, 2.0, 10.0),
+                           0.5, 0.1);
+
+    for i in 0..200 {
+        let (x, y, z) = ((i as f64) * 1.37 - 80.0, (i as f64) * -0.53 + 9.0, (i as f64) * 0.29);
+        let c = control.get_value2d(x, y);
+        assert_eq!(ngen.get_value2d(x, y), select_2d(c, &low, &high, 0.5, 0.1, x, y));
+        let c = control.get_value3d(x, y, z);
+        assert_eq!(ngen.get_value3d(x, y, z), select_3d(c, &low, &high, 0.5, 0.1, x, y, z));
+    }
+}
+
+// Only the side that's picked is sampled
+#[test]
+fn select_picks_one_side() {
+    let low = Select::new(0.2, -1.0, Unused, 0.5, 0.1);
+    let high = Select::new(0.8, Unused, 1.0, 0.5, 0.1);
+    assert_eq!((low.get_value1d(0.0), low.get_value4d(0.0, 0.0, 0.0, 0.0)), (-1.0, -1.0));
+    assert_eq!((high.get_value1d(0.0), high.get_value4d(0.0, 0.0, 0.0, 0.0)), (1.0, 1.0));
+
+    // halfway through the falloff is the average
+    assert_eq!(Select::new(0.5, -1.0, 1.0, 0.5, 0.1).get_value2d(0.0, 0.0), 0.0);
+}
+
+#[test]
+fn zero_falloff_is_a_hard_edge() {
+    for &(c, n) in [(0.49, -1.0), (0.5, 1.0), (0.51, 1.0)].iter() {
+        assert_eq!(Select::new(c, -1.0, 1.0, 0.5, 0.0).get_value2d(0.0, 0.0), n);
+    }
+}
+
+// Selects are generators, so they nest in each other and in fractals
+#[test]
+fn selects_nest() {
+    let inner = Select::new(FBM::from_seed(4, 4, 0.5, 2.0, 40.0), 0.0, 1.0, 0.5, 0.05);
+    let outer = Select::new(Billow::from_seed(5, 4, 0.5, 2.0, 40.0), inner, 0.25, 0.3, 0.0);
+    let mut ngen = FBM::from_source(outer, 3, 0.5, 2.0);
+    ngen.source_mut().set_threshold(0.35);
+    assert_eq!(ngen.source().get_threshold(), 0.35);
+
+    let values: Vec<f64> = (0..500).map(|i| ngen.get_value3d((i as f64) * 0.7, (i as f64) * 0.3, 1.5)).collect();
+    assert!(values.iter().all(|n| (0.0..=1.0).contains(n)));
+    assert!(values.iter().any(|&n| n != values[0]));
+}
@@ -0,0 +1,91 @@
+// setters.rs
+
+mod common;
+
+use noise::error::BuildError;
+use noise::gen::simplex::Simplex;
+use noise::gen::fbm::FBM;
+use noise::gen::billow::Billow;
+use noise::gen::ridgedmulti::RidgedMulti;
+use noise::gen::hybridmulti::HybridMulti;
+use noise::gen::erosion::Erosion;
+use common::assert_same;
+
+#[test]
+fn simplex_zoom_round_trips() {
+    let mut ngen = Simplex::from_seed(1, 10.0);
+    ngen.set_zoom(40.0);
+    assert_eq!(ngen.get_zoom(), 40.0);
+    assert_same(&ngen, &Simplex::from_seed(1, 40.0));
+}
+
+#[test]
+fn fbm_setters_match_new() {
+    let mut ngen = FBM::from_seed(2, 3, 0.7, 1.5, 10.0);
+    ngen.set_octaves(6).unwrap();
+    ngen.set_persistence(0.5);
+    ngen.set_lacunarity(2.0).unwrap();
+    ngen.set_zoom(20.0).unwrap();
+    assert_eq!(ngen.get_seed(), 2);
+    assert_eq!((ngen.get_octaves(), ngen.get_persistence(), ngen.get_lacunarity(), ngen.get_zoom()), (6, 0.5, 2.0, 20.0));
+    assert_same(&ngen, &FBM::from_seed(2, 6, 0.5, 2.0, 20.0));
+}
+
+#[test]
+fn billow_setters_match_new() {
+    let mut ngen = Billow::from_seed(3, 9, 0.3, 2.5, 10.0);
+    ngen.set_persistence(0.5);
+    ngen.set_octaves(4).unwrap();
+    ngen.set_lacunarity(2.0).unwrap();
+    ngen.set_zoom(5.0).unwrap();
+    assert_same(&ngen, &Billow::from_seed(3, 4, 0.5, 2.0, 5.0));
+}
+
+#[test]
+fn ridgedmulti_setters_match_new() {
+    let mut ngen = RidgedMulti::from_seed(4, 3, 2.0, 2.5, 0.8, 1.0, 10.0);
+    ngen.set_octaves(8).unwrap();
+    ngen.set_gain(1.7);
+    ngen.set_lacunarity(1.9).unwrap();
+    ngen.set_offset(1.0);
+    ngen.set_h(0.75);
+    ngen.set_zoom(30.0).unwrap();
+    assert_eq!((ngen.get_octaves(), ngen.get_gain(), ngen.get_lacunarity(), ngen.get_offset(), ngen.get_h()),
+               (8, 1.7, 1.9, 1.0, 0.75));
+    assert_same(&ngen, &RidgedMulti::from_seed(4, 8, 1.7, 1.9, 1.0, 0.75, 30.0));
+}
+
+// The setters take the builders' checks, and a rejected value changes nothing
+#[test]
+fn setters_reject_what_builders_do() {
+    let mut ngen = FBM::from_seed(6, 4, 0.5, 2.0, 10.0);
+    assert_eq!(ngen.set_octaves(-3), Err(BuildError::Octaves(-3)));
+    assert_eq!(ngen.set_octaves(0), Err(BuildError::Octaves(0)));
+    assert_eq!(ngen.set_zoom(0.0), Err(BuildError::Zoom(0.0)));
+    assert!(matches!(ngen.set_lacunarity(f64::NAN), Err(BuildError::Lacunarity(_))));
+    assert_same(&ngen, &FBM::from_seed(6, 4, 0.5, 2.0, 10.0));
+
+    let mut ngen = Billow::from_seed(7, 4, 0.5, 2.0, 10.0);
+    assert_eq!(ngen.set_lacunarity(-2.0), Err(BuildError::Lacunarity(-2.0)));
+    assert_eq!(ngen.set_zoom(f64::INFINITY), Err(BuildError::Zoom(f64::INFINITY)));
+    assert_same(&ngen, &Billow::from_seed(7, 4, 0.5, 2.0, 10.0));
+
+    let mut ngen = RidgedMulti::from_seed(8, 4, 1.7, 1.9, 1.0, 0.75, 10.0);
+    assert_eq!(ngen.set_octaves(0), Err(BuildError::Octaves(0)));
+    assert_same(&ngen, &RidgedMulti::from_seed(8, 4, 1.7, 1.9, 1.0, 0.75, 10.0));
+
+    let mut ngen = HybridMulti::from_seed(9, 4, 2.0, 0.7, 0.25, 10.0);
+    assert_eq!(ngen.set_lacunarity(0.0), Err(BuildError::Lacunarity(0.0)));
+    assert_eq!(ngen.get_lacunarity(), 2.0);
+
+    let mut ngen = Erosion::from_seed(10, 4, 0.5, 2.0, 10.0);
+    assert_eq!(ngen.set_zoom(0.0), Err(BuildError::Zoom(0.0)));
+    assert_eq!(ngen.get_zoom(), 10.0);
+}
+
+#[test]
+fn source_mut_changes_the_source() {
+    let mut ngen = FBM::from_source(Simplex::from_seed(5, 10.0), 4, 0.5, 2.0);
+    ngen.source_mut().set_seed(6);
+    assert_same(&ngen, &FBM::from_source(Simplex::from_seed(6, 10.0), 4, 0.5, 2.0));
+}
@@ -0,0 +1,68 @@
+// simplex.rs
+
+use noise::gen::NoiseGen;
+use noise::gen::simplex::Simplex;
+use noise::lcgrng::LCG;
+
+// The 24 gradients of 3D OpenSimplex, as in Simplex
+static GRAD_3D: [i8; 72] = [
+    -11,  4,  4,   -4,  11,  4,   -4,  4,  11,
+     11,  4,  4,    4,  11,  4,    4,  4,  11,
+    -11, -4,  4,   -4, -11,  4,   -4, -4,  11,
+     11, -4,  4,    4, -11,  4,    4, -4,  11,
+    -11,  4, -4,   -4,  11, -4,   -4,  4, -11,
+     11,  4, -4,    4,  11, -4,    4,  4, -11,
+    -11, -4, -4,   -4, -11, -4,   -4, -4, -11,
+     11, -4, -4,    4, -11, -4,    4, -4, -11,
+];
+
+fn perm(seed: u32) -> [u8; 256] {
+    let mut perm = [0; 256];
+    for (i, p) in perm.iter_mut().enumerate() {
+        *p = i as u8;
+    }
+    LCG::from_seed(seed).shuffle(&mut perm);
+    perm
+}
+
+// 3D OpenSimplex summed over every lattice point around the super cell, with
+// no choice of which ones can contribute
+fn brute_force_3d(perm: &[u8; 256], x: f64, y: f64, z: f64) -> f64 {
+    const SQUISH: f64 = 1.0 / 3.0;
+    let stretch = (x + y + z) * (-1.0 / 6.0);
+    let (xsb, ysb, zsb) = ((x + stretch).floor() as i64, (y + stretch).floor() as i64, (z + stretch).floor() as i64);
+
+    let mut value = 0.0;
+    for i in xsb-1..xsb+3 {
+        for j in ysb-1..ysb+3 {
+            for k in zsb-1..zsb+3 {
+                let squish = ((i + j + k) as f64) * SQUISH;
+                let d = [x - (i as f64) - squish, y - (j as f64) - squish, z - (k as f64) - squish];
+                let attn = 2.0 - d.iter().map(|d| d * d).sum::<f64>();
+                if attn <= 0.0 {
+                    continue;
+                }
+
+                let h = [i, j, k].iter().fold(0, |h, &l| perm[((h as usize) + ((l & 0xFF) as usize)) & 0xFF]);
+                let g = &GRAD_3D[((h as usize) % 24) * 3..][..3];
+                let ext: f64 = g.iter().zip(d.iter()).map(|(&g, d)| (g as f64) * d).sum();
+                value += attn * attn * attn * attn * ext;
+            }
+        }
+    }
+    value / 103.0
+}
+
+#[test]
+fn simplex_3d_matches_brute_force() {
+    let ngen = Simplex::from_seed(1, 1.0);
+    let perm = perm(1);
+    let mut rng = LCG::from_seed(11);
+    let mut coord = || (rng.next_u32() as f64) * (200.0 / 4294967296.0) - 100.0;
+
+    for _ in 0..5000 {
+        let (x, y, z) = (coord(), coord(), coord());
+        let expect = brute_force_3d(&perm, x, y, z);
+        assert!((ngen.get_value3d(x, y, z) - expect).abs() < 1e-12, "({}, {}, {})", x, y, z);
+    }
+}
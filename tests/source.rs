@@ -0,0 +1,48 @@
+// source.rs
+
+mod common;
+
+use noise::gen::NoiseGen;
+use noise::gen::simplex::Simplex;
+use noise::gen::fbm::FBM;
+use noise::gen::billow::Billow;
+use noise::gen::ridgedmulti::RidgedMulti;
+use noise::gen::voronoi::Voronoi;
+use common::assert_same;
+
+// from_seed also seeds the octave offsets with the simplex seed
+#[test]
+fn from_source_matches_from_seed() {
+    let mut fbm = FBM::from_source(Simplex::from_seed(1, 10.0), 6, 0.5, 2.0);
+    fbm.set_octave_seed(1);
+    assert_same(&fbm, &FBM::from_seed(1, 6, 0.5, 2.0, 10.0));
+
+    let mut billow = Billow::from_source(Simplex::from_seed(2, 10.0), 6, 0.5, 2.0);
+    billow.set_octave_seed(2);
+    assert_same(&billow, &Billow::from_seed(2, 6, 0.5, 2.0, 10.0));
+
+    let mut ridged = RidgedMulti::from_source(Simplex::from_seed(3, 10.0), 6, 1.7, 1.9, 1.0, 0.75);
+    ridged.set_octave_seed(3);
+    assert_same(&ridged, &RidgedMulti::from_seed(3, 6, 1.7, 1.9, 1.0, 0.75, 10.0));
+}
+
+#[test]
+fn one_octave_is_the_source() {
+    let ngen = FBM::from_source(Voronoi::from_seed(4, 10.0), 1, 0.5, 2.0);
+    for i in 0..64 {
+        let x = (i as f64) * 0.37;
+        let n = ngen.source().get_value2d(x, 2.0*x);
+        assert_eq!(ngen.get_value2d(x, 2.0*x), (n + 1.0) * 0.5);
+    }
+}
+
+#[test]
+fn fractals_nest() {
+    let inner = FBM::from_seed(5, 4, 0.5, 2.0, 10.0);
+    let ngen = Billow::from_source(inner, 3, 0.5, 2.0);
+    for i in 0..64 {
+        let x = (i as f64) * 0.37;
+        let n = ngen.get_value3d(x, -x, 0.25*x);
+        assert!((0.0..=1.0).contains(&n), "{} out of range", n);
+    }
+}
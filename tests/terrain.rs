@@ -0,0 +1,84 @@
+// terrain.rs
+
+mod common;
+
+use noise::error::BuildError;
+use noise::gen::NoiseGen;
+use noise::gen::simplex::Simplex;
+use noise::gen::hybridmulti::HybridMulti;
+use noise::gen::heteroterrain::HeteroTerrain;
+use common::assert_same;
+
+// Values stay in [0,1] and still cover a fair part of it
+fn check_range<T: NoiseGen>(ngen: &T) {
+    let (mut lo, mut hi) = (1.0_f64, 0.0_f64);
+    for j in 0..100 {
+        for i in 0..100 {
+            let (x, y) = (i as f64, j as f64);
+            for n in [ngen.get_value2d(x, y), ngen.get_value3d(x, y, 0.5*x), ngen.get_value4d(x, y, 0.5*y, 0.25*x)].iter() {
+                assert!((0.0..=1.0).contains(n), "{} out of range", n);
+                lo = lo.min(*n);
+                hi = hi.max(*n);
+            }
+        }
+    }
+    assert!(hi - lo > 0.1, "only covered [{}, {}]", lo, hi);
+}
+
+#[test]
+fn hybridmulti_range() {
+    check_range(&HybridMulti::from_seed(1, 8, 2.0, 0.7, 0.25, 20.0));
+    check_range(&HybridMulti::from_seed(2, 4, 1.9, 0.0, 1.0, 20.0));
+}
+
+#[test]
+fn heteroterrain_range() {
+    check_range(&HeteroTerrain::from_seed(3, 8, 2.0, 0.7, 0.25, 20.0));
+    check_range(&HeteroTerrain::from_seed(4, 4, 1.9, -0.3, 0.9, 20.0));
+}
+
+// With one octave both are the source plus the offset, stretched over [0,1]
+#[test]
+fn one_octave_is_the_source() {
+    let simp = Simplex::from_seed(5, 20.0);
+    let hybrid = HybridMulti::from_source(Simplex::from_seed(5, 20.0), 1, 2.0, 0.7, 0.25);
+    let hetero = HeteroTerrain::from_source(Simplex::from_seed(5, 20.0), 1, 2.0, 0.7, 0.25);
+    for i in 0..64 {
+        let x = (i as f64) * 0.37;
+        let n = (simp.get_value2d(x, -x) + 1.0) * 0.5;
+        assert!((hybrid.get_value2d(x, -x) - n).abs() < 1e-12);
+        assert!((hetero.get_value2d(x, -x) - n).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn builders_match_from_seed() {
+    let hybrid = HybridMulti::builder().seed(6).octaves(5).lacunarity(1.9).offset(0.5).h(0.3).zoom(10.0).build().unwrap();
+    assert_same(&hybrid, &HybridMulti::from_seed(6, 5, 1.9, 0.5, 0.3, 10.0));
+
+    let hetero = HeteroTerrain::builder().seed(7).octaves(5).lacunarity(1.9).offset(0.5).h(0.3).zoom(10.0).build().unwrap();
+    assert_same(&hetero, &HeteroTerrain::from_seed(7, 5, 1.9, 0.5, 0.3, 10.0));
+
+    assert_eq!(HybridMulti::builder().octaves(0).build().err(), Some(BuildError::Octaves(0)));
+    assert_eq!(HeteroTerrain::builder().lacunarity(0.0).build().err(), Some(BuildError::Lacunarity(0.0)));
+    assert_eq!(HeteroTerrain::builder().zoom(0.0).build().err(), Some(BuildError::Zoom(0.0)));
+}
+
+#[test]
+fn setters_match_from_seed() {
+    let mut hybrid = HybridMulti::from_seed(8, 3, 2.5, 0.2, 1.0, 5.0);
+    hybrid.set_octaves(7).unwrap();
+    hybrid.set_lacunarity(2.0).unwrap();
+    hybrid.set_offset(0.7);
+    hybrid.set_h(0.25);
+    hybrid.set_zoom(10.0).unwrap();
+    assert_same(&hybrid, &HybridMulti::from_seed(8, 7, 2.0, 0.7, 0.25, 10.0));
+
+    let mut hetero = HeteroTerrain::from_seed(9, 3, 2.5, 0.2, 1.0, 5.0);
+    hetero.set_octaves(7).unwrap();
+    hetero.set_lacunarity(2.0).unwrap();
+    hetero.set_offset(0.7);
+    hetero.set_h(0.25);
+    hetero.set_zoom(10.0).unwrap();
+    assert_same(&hetero, &HeteroTerrain::from_seed(9, 7, 2.0, 0.7, 0.25, 10.0));
+}
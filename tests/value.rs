@@ -0,0 +1,104 @@
+// value.rs
+
+mod common;
+
+use noise::gen::NoiseGen;
+use noise::gen::value::{Value, Curve};
+use common::points;
+
+const CURVES: [Curve; 3] = [Curve::Linear, Curve::Cubic, Curve::Quintic];
+
+fn with_curve(seed: u32, curve: Curve) -> Value {
+    let mut ngen = Value::from_seed(seed, 1.0);
+    ngen.set_curve(curve);
+    ngen
+}
+
+#[test]
+fn value_range() {
+    for &curve in CURVES.iter() {
+        let ngen = with_curve(1, curve);
+        for (x, y, z, w) in points() {
+            for n in [ngen.get_value1d(x), ngen.get_value2d(x, y), ngen.get_value3d(x, y, z), ngen.get_value4d(x, y, z, w)].iter() {
+                assert!((-1.0..=1.0).contains(n), "{} out of range", n);
+            }
+        }
+    }
+}
+
+// Every curve passes through the lattice values and is halfway at the middle
+// of a cell, so they only differ in between
+#[test]
+fn curves_agree_on_lattice_and_midpoints() {
+    let linear = with_curve(2, Curve::Linear);
+    for &curve in CURVES.iter() {
+        let ngen = with_curve(2, curve);
+        for i in -20..20 {
+            for &o in [0.0, 0.5].iter() {
+                let (x, y, z, w) = (i as f64 + o, (3 - i) as f64 + o, (i * 7 % 11) as f64 + o, (i / 3) as f64 + o);
+                assert_eq!(ngen.get_value1d(x), linear.get_value1d(x));
+                assert_eq!(ngen.get_value2d(x, y), linear.get_value2d(x, y));
+                assert_eq!(ngen.get_value3d(x, y, z), linear.get_value3d(x, y, z));
+                assert_eq!(ngen.get_value4d(x, y, z, w), linear.get_value4d(x, y, z, w));
+            }
+        }
+    }
+}
+
+#[test]
+fn linear_is_piecewise_linear() {
+    let ngen = with_curve(3, Curve::Linear);
+    for i in -20..20 {
+        let x = i as f64;
+        let (a, b) = (ngen.get_value1d(x), ngen.get_value1d(x + 1.0));
+        assert!((ngen.get_value1d(x + 0.25) - (0.75*a + 0.25*b)).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn value_is_continuous() {
+    let e = 1e-9;
+    for &curve in CURVES.iter() {
+        let ngen = with_curve(4, curve);
+        for i in -10..10 {
+            let c = i as f64;
+            let (y, z, w) = (0.3 * c + 0.1, 0.7 - 0.2 * c, 0.45);
+            assert!((ngen.get_value1d(c - e) - ngen.get_value1d(c + e)).abs() < 1e-8);
+            assert!((ngen.get_value2d(c - e, y) - ngen.get_value2d(c + e, y)).abs() < 1e-8);
+            assert!((ngen.get_value3d(y, c - e, z) - ngen.get_value3d(y, c + e, z)).abs() < 1e-8);
+            assert!((ngen.get_value4d(y, z, w, c - e) - ngen.get_value4d(y, z, w, c + e)).abs() < 1e-8);
+        }
+    }
+}
+
+#[test]
+fn value_setters_match_new() {
+    let mut ngen = Value::from_seed(5, 10.0);
+    ngen.set_seed(6);
+    ngen.set_zoom(2.5);
+    ngen.set_curve(Curve::Cubic);
+    assert_eq!((ngen.get_seed(), ngen.get_zoom(), ngen.get_curve()), (6, 2.5, Curve::Cubic));
+
+    let mut new = Value::from_seed(6, 2.5);
+    new.set_curve(Curve::Cubic);
+    for (x, y, z, w) in points() {
+        assert_eq!(ngen.get_value1d(x), new.get_value1d(x));
+        assert_eq!(ngen.get_value2d(x, y), new.get_value2d(x, y));
+        assert_eq!(ngen.get_value3d(x, y, z), new.get_value3d(x, y, z));
+        assert_eq!(ngen.get_value4d(x, y, z, w), new.get_value4d(x, y, z, w));
+    }
+}
+
+// With a single pass through the permutation every lattice column was the one
+// before it shifted along
+#[test]
+fn columns_are_not_shifted_copies() {
+    let ngen = Value::from_seed(7, 1.0);
+    let col = |x: i64| (0..512).map(|y| ngen.get_value2d(x as f64, y as f64)).collect::<Vec<f64>>();
+    for x in 0..8 {
+        let (a, b) = (col(x), col(x + 1));
+        for d in 0..256 {
+            assert!((0..256).any(|y| a[y + d] != b[y]));
+        }
+    }
+}
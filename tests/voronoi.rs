@@ -0,0 +1,242 @@
+// voronoi.rs
+
+mod common;
+
+use noise::error::BuildError;
+use noise::gen::NoiseGen;
+use noise::gen::voronoi::{Voronoi, Output, Metric};
+use common::points;
+
+const METRICS: [Metric; 5] = [Metric::Euclidean, Metric::EuclideanSquared, Metric::Manhattan, Metric::Chebyshev, Metric::Minkowski(3.0)];
+
+fn with(seed: u32, jitter: f64, output: Output, metric: Metric) -> Voronoi {
+    let mut ngen = Voronoi::from_seed(seed, 1.0);
+    ngen.set_jitter(jitter);
+    ngen.set_output(output);
+    ngen.set_metric(metric);
+    ngen
+}
+
+fn distance(metric: Metric, d: &[f64]) -> f64 {
+    match metric {
+        Metric::Euclidean => d.iter().map(|a| a*a).sum::<f64>().sqrt(),
+        Metric::EuclideanSquared => d.iter().map(|a| a*a).sum(),
+        Metric::Manhattan => d.iter().map(|a| a.abs()).sum(),
+        Metric::Chebyshev => d.iter().fold(0.0, |m, a| a.abs().max(m)),
+        Metric::Minkowski(p) => d.iter().map(|a| a.abs().powf(p)).sum::<f64>().powf(1.0/p),
+    }
+}
+
+// Without jitter the feature points are the cell centres, so F1 and F2 can be
+// found by checking every centre nearby
+#[test]
+fn zero_jitter_matches_brute_force() {
+    for &metric in METRICS.iter() {
+        let f1 = with(1, 0.0, Output::F1, metric);
+        let f2 = with(1, 0.0, Output::F2, metric);
+        for (x, y, _, _) in points() {
+            let mut dists = Vec::new();
+            for cy in (y.floor() as i64 - 3)..=(y.floor() as i64 + 3) {
+                for cx in (x.floor() as i64 - 3)..=(x.floor() as i64 + 3) {
+                    dists.push(distance(metric, &[cx as f64 + 0.5 - x, cy as f64 + 0.5 - y]));
+                }
+            }
+            dists.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            assert!((f1.get_value2d(x, y) - dists[0]).abs() < 1e-12);
+            assert!((f2.get_value2d(x, y) - dists[1]).abs() < 1e-12);
+        }
+    }
+}
+
+#[test]
+fn outputs_agree() {
+    for &metric in METRICS.iter() {
+        let f1 = with(2, 1.0, Output::F1, metric);
+        let f2 = with(2, 1.0, Output::F2, metric);
+        let diff = with(2, 1.0, Output::F2MinusF1, metric);
+        let prod = with(2, 1.0, Output::F1TimesF2, metric);
+        for (x, y, z, _) in points() {
+            let (a, b) = (f1.get_value3d(x, y, z), f2.get_value3d(x, y, z));
+            assert!(0.0 <= a && a <= b);
+            assert_eq!(diff.get_value3d(x, y, z), b - a);
+            assert_eq!(prod.get_value3d(x, y, z), a * b);
+        }
+    }
+}
+
+// The same feature points measured differently
+#[test]
+fn metrics_agree() {
+    let euclid = with(3, 1.0, Output::F1, Metric::Euclidean);
+    let squared = with(3, 1.0, Output::F1, Metric::EuclideanSquared);
+    let manhattan = with(3, 1.0, Output::F1, Metric::Manhattan);
+    let chebyshev = with(3, 1.0, Output::F1, Metric::Chebyshev);
+    let mink1 = with(3, 1.0, Output::F1, Metric::Minkowski(1.0));
+    let mink2 = with(3, 1.0, Output::F1, Metric::Minkowski(2.0));
+    for (x, y, _, _) in points() {
+        let e = euclid.get_value2d(x, y);
+        assert!((squared.get_value2d(x, y) - e*e).abs() < 1e-12);
+        assert!((mink2.get_value2d(x, y) - e).abs() < 1e-12);
+        assert!((mink1.get_value2d(x, y) - manhattan.get_value2d(x, y)).abs() < 1e-12);
+        assert!(chebyshev.get_value2d(x, y) <= e + 1e-12);
+        assert!(e <= manhattan.get_value2d(x, y) + 1e-12);
+    }
+}
+
+#[test]
+fn cell_values_in_range() {
+    let ngen = Voronoi::from_seed(4, 3.0);
+    for (x, y, z, w) in points() {
+        for n in [ngen.get_value1d(x), ngen.get_value2d(x, y), ngen.get_value3d(x, y, z), ngen.get_value4d(x, y, z, w)].iter() {
+            assert!((-1.0..=1.0).contains(n), "{} out of range", n);
+        }
+    }
+}
+
+// Feature points used to be jittered by the same amount on every axis
+#[test]
+fn jitter_is_per_axis() {
+    let ngen = with(5, 1.0, Output::F1, Metric::Euclidean);
+    let off_diagonal = (0..100).filter(|&i| {
+        // the feature point of cell (i, 0) is where F1 in that cell is smallest
+        let mut best = (f64::INFINITY, 0.0, 0.0);
+        for s in 0..50 {
+            for t in 0..50 {
+                let (x, y) = (i as f64 + (s as f64 + 0.5) / 50.0, (t as f64 + 0.5) / 50.0);
+                let n = ngen.get_value2d(x, y);
+                if n < best.0 {
+                    best = (n, x - i as f64, y);
+                }
+            }
+        }
+        (best.1 - best.2).abs() > 0.1
+    }).count();
+    assert!(off_diagonal > 50);
+}
+
+#[test]
+fn voronoi_builder_and_setters() {
+    let built = Voronoi::builder()
+        .seed(6)
+        .zoom(4.0)
+        .jitter(0.6)
+        .output(Output::F2MinusF1)
+        .metric(Metric::Manhattan)
+        .build()
+        .unwrap();
+    let mut set = with(6, 0.6, Output::F2MinusF1, Metric::Manhattan);
+    set.set_zoom(4.0);
+    assert_eq!(set.get_zoom(), 4.0);
+    for (x, y, z, w) in points() {
+        assert_eq!(built.get_value1d(x), set.get_value1d(x));
+        assert_eq!(built.get_value2d(x, y), set.get_value2d(x, y));
+        assert_eq!(built.get_value3d(x, y, z), set.get_value3d(x, y, z));
+        assert_eq!(built.get_value4d(x, y, z, w), set.get_value4d(x, y, z, w));
+    }
+
+    assert_eq!(Voronoi::builder().jitter(1.5).build().err(), Some(BuildError::Jitter(1.5)));
+    assert_eq!(Voronoi::builder().metric(Metric::Minkowski(0.0)).build().err(), Some(BuildError::Minkowski(0.0)));
+    assert_eq!(Voronoi::builder().metric(Metric::Minkowski(0.5)).build().err(), Some(BuildError::Minkowski(0.5)));
+    assert!(Voronoi::builder().metric(Metric::Minkowski(1.0)).build().is_ok());
+    assert_eq!(Voronoi::builder().zoom(0.0).build().err(), Some(BuildError::Zoom(0.0)));
+}
+
+#[test]
+fn cell_query_matches_the_noise() {
+    let mut f1 = with(7, 1.0, Output::F1, Metric::Euclidean);
+    f1.set_zoom(3.0);
+    let mut ngen = with(7, 1.0, Output::CellValue, Metric::Euclidean);
+    ngen.set_zoom(3.0);
+
+    for (x, y, z, _) in points() {
+        let c = ngen.get_cell2d(x, y);
+        assert_eq!(c.distance, f1.get_value2d(x, y));
+        let d = ((c.point[0] - x).powi(2) + (c.point[1] - y).powi(2)).sqrt() / 3.0;
+        assert!((c.distance - d).abs() < 1e-12);
+        for axis in 0..2 {
+            assert_eq!((c.point[axis] / 3.0).floor() as i64, c.cell[axis]);
+        }
+
+        let c = ngen.get_cell3d(x, y, z);
+        assert_eq!(c.distance, f1.get_value3d(x, y, z));
+        // moving towards the feature point stays in the same cell
+        let q = [(x + c.point[0]) * 0.5, (y + c.point[1]) * 0.5, (z + c.point[2]) * 0.5];
+        assert_eq!(ngen.get_cell3d(q[0], q[1], q[2]).id, c.id);
+    }
+}
+
+// IDs are meant to be stored, so they mustn't change between versions, and
+// every cell needs its own
+#[test]
+fn cell_ids_are_stable() {
+    let ngen = Voronoi::from_seed(8, 1.0);
+    let c = ngen.get_cell2d(0.5, 0.5);
+    assert_eq!(c.cell, [0, 0]);
+    assert_eq!(c.id, 2334541789286733901);
+
+    let mut ids: Vec<u64> = points().iter().map(|&(x, y, _, _)| ngen.get_cell2d(x, y).id).collect();
+    let cells = points().iter().map(|&(x, y, _, _)| ngen.get_cell2d(x, y).cell).collect::<std::collections::HashSet<_>>().len();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), cells);
+}
+
+// Cells -1 and 0, -2 and 1 and so on are as far apart as any others, so
+// their feature points mustn't mirror each other across the origin
+#[test]
+fn negative_cells_have_their_own_points() {
+    let ngen = with(11, 1.0, Output::F1, Metric::Euclidean);
+    for (x, y, _, _) in points() {
+        let c = ngen.get_cell2d(x, y);
+        // The same spot within the mirrored cell along x
+        let mirrored = [c.point[0] - (2 * c.cell[0] + 1) as f64, c.point[1]];
+        assert_ne!(ngen.get_cell2d(mirrored[0], mirrored[1]).point, mirrored);
+    }
+}
+
+// On a regular grid the cells are the lattice squares
+#[test]
+fn zero_jitter_edges_are_the_lattice() {
+    let ngen = with(9, 0.0, Output::Edge, Metric::Euclidean);
+    for (x, y, z, _) in points() {
+        let to_edge = |a: f64| (a - a.floor()).min(a.ceil() - a);
+        assert!((ngen.get_value2d(x, y) - to_edge(x).min(to_edge(y))).abs() < 1e-12);
+        assert!((ngen.get_value3d(x, y, z) - to_edge(x).min(to_edge(y)).min(to_edge(z))).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn edge_distance_is_exact() {
+    let edge = with(10, 1.0, Output::Edge, Metric::Euclidean);
+    let diff = with(10, 1.0, Output::F2MinusF1, Metric::Euclidean);
+    let cells = with(10, 1.0, Output::CellValue, Metric::Euclidean);
+    for (x, y, _, _) in points().into_iter().step_by(3) {
+        let e = edge.get_value2d(x, y);
+        // F2-F1 only ever underestimates it
+        assert!(e >= diff.get_value2d(x, y) * 0.5 - 1e-12);
+
+        // Nothing closer than the edge is in another cell, but something just past it is
+        let id = cells.get_cell2d(x, y).id;
+        let mut crossed = false;
+        for i in 0..720 {
+            let a = (i as f64).to_radians() * 0.5;
+            assert_eq!(cells.get_cell2d(x + 0.999*e*a.cos(), y + 0.999*e*a.sin()).id, id);
+            crossed |= cells.get_cell2d(x + 1.001*e*a.cos(), y + 1.001*e*a.sin()).id != id;
+        }
+        assert!(crossed);
+    }
+}
+
+#[test]
+fn border_ramps_down_from_the_edges() {
+    let edge = with(11, 1.0, Output::Edge, Metric::Euclidean);
+    let border = with(11, 1.0, Output::Border(0.1), Metric::Euclidean);
+    for (x, y, z, _) in points() {
+        let e = edge.get_value3d(x, y, z);
+        let b = border.get_value3d(x, y, z);
+        assert!((-1.0..=1.0).contains(&b));
+        assert!((b - if e < 0.1 { 1.0 - 20.0*e } else { -1.0 }).abs() < 1e-12);
+    }
+
+    assert_eq!(Voronoi::builder().output(Output::Border(0.0)).build().err(), Some(BuildError::BorderWidth(0.0)));
+}